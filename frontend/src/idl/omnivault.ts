@@ -1824,6 +1824,26 @@ export type Omnivault = {
         ]
       }
     },
+    {
+      "name": "mintTvl",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "mint",
+            "type": "pubkey"
+          },
+          {
+            "name": "totalTvl",
+            "type": "u64"
+          },
+          {
+            "name": "depositCap",
+            "type": "u64"
+          }
+        ]
+      }
+    },
     {
       "name": "rebalanceExecuted",
       "type": {
@@ -2075,7 +2095,7 @@ export type Omnivault = {
             "type": "u64"
           },
           {
-            "name": "totalTvl",
+            "name": "legacyTotalTvl",
             "type": "u64"
           },
           {
@@ -2099,6 +2119,32 @@ export type Omnivault = {
             "type": {
               "vec": "u16"
             }
+          },
+          {
+            "name": "pendingAuthority",
+            "type": "pubkey"
+          },
+          {
+            "name": "configDelay",
+            "type": "i64"
+          },
+          {
+            "name": "mintTvls",
+            "type": {
+              "vec": {
+                "defined": {
+                  "name": "mintTvl"
+                }
+              }
+            }
+          },
+          {
+            "name": "pauseFlags",
+            "type": "u8"
+          },
+          {
+            "name": "version",
+            "type": "u8"
           }
         ]
       }
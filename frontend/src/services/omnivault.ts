@@ -35,15 +35,22 @@ export const RiskProfile = {
 export type RiskProfile = typeof RiskProfile[keyof typeof RiskProfile];
 
 // Type definitions based on the actual generated IDL structure
+// TVL is tracked per asset mint, in that mint's base units
+export interface MintTvl {
+  mint: PublicKey;
+  totalTvl: BN;
+  depositCap: BN;
+}
+
 export interface VaultStore {
   authority: PublicKey;
   totalVaults: BN;
-  totalTvl: BN;
   feeRate: number;
   bump: number;
   lastGlobalRebalance: BN;
   emergencyPause: boolean;
   supportedChains: number[];
+  mintTvls: MintTvl[];
 }
 
 export interface Vault {
@@ -831,6 +838,13 @@ export class OmniVaultService {
     }
   }
 
+  // Store-wide TVL of one asset mint, in its base units
+  async getMintTvl(mint: PublicKey): Promise<BN> {
+    const vaultStore = await this.getVaultStore();
+    const entry = vaultStore?.mintTvls.find((mintTvl) => mintTvl.mint.equals(mint));
+    return entry ? entry.totalTvl : new BN(0);
+  }
+
   // Fetch vault data
   async getVault(owner: PublicKey, vaultId: number): Promise<Vault | null> {
     try {
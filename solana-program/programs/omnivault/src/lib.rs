@@ -1,10 +1,20 @@
+// #[program] expands its IDL instructions at crate level, and Anchor 0.31's
+// generated code still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_2022::spl_token_2022::onchain::invoke_transfer_checked;
+use anchor_spl::token_interface::{self, Burn, CloseAccount, Mint, MintTo, TokenAccount, TokenInterface};
 use anchor_lang::solana_program::{
-    program::invoke_signed,
+    hash::hash,
+    program::{get_return_data, invoke, invoke_signed},
     sysvar::{clock::Clock},
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
     instruction::{AccountMeta, Instruction},
-    system_instruction,
 };
 use std::str::FromStr;
 
@@ -12,8 +22,41 @@ declare_id!("BxpNexvSRuUoaSwdff5aEmCGX7LBDhGPtA79VVraPtqr");
 
 // LayerZero V2 Configuration
 const LAYERZERO_ENDPOINT: &str = "LZ1ZeTMZZnKWEcG2ukQpvJE2QnLEyV5uYPVfPjTvZmV";  // LayerZero Devnet Endpoint
+const LZ_OPTIONS_TYPE_3: u16 = 3;
+const LZ_EXECUTOR_WORKER_ID: u8 = 1;
+const LZ_OPTION_LZ_RECEIVE: u8 = 1;
+const LZ_OPTION_NATIVE_DROP: u8 = 2;
+const LZ_OPTION_LZ_COMPOSE: u8 = 3;
 const MAX_CROSS_CHAIN_QUERIES: u8 = 10;
+// CCTP V2 message header: version, source/destination domain, nonce, sender, recipient, destination caller
+const CCTP_DESTINATION_CALLER_OFFSET: usize = 108;
+const CCTP_MESSAGE_HEADER_LEN: usize = 140;
 const MIN_REBALANCE_INTERVAL: i64 = 3600; // 1 hour
+const PRICE_PRECISION: u64 = 1_000_000_000; // Share price scale (1e9 = 1 asset unit per share)
+const SECONDS_PER_YEAR: i64 = 31_536_000;
+const MAX_MANAGEMENT_FEE_BPS: u16 = 500; // 5% per year
+const MAX_PERFORMANCE_FEE_BPS: u16 = 3000; // 30% of yield above the high-water mark
+const MAX_WITHDRAWAL_FEE_RATE: u16 = 1000; // 10% hard cap on VaultStore.fee_rate
+const MAX_SUPPORTED_CHAINS: usize = 10;
+const MAX_KEEPERS: usize = 10;
+const MAX_GUARDIANS: usize = 5;
+const MAX_FEE_MANAGERS: usize = 5;
+const DEFAULT_CONFIG_DELAY: i64 = 86400; // 24 hours
+const MIN_CONFIG_DELAY: i64 = 3600; // 1 hour
+const MAX_CONFIG_DELAY: i64 = 2_592_000; // 30 days
+const MAX_MULTISIG_SIGNERS: usize = 10;
+const MAX_VAULT_ASSETS: usize = 8; // Additional mints per vault beyond asset_mint
+const MAX_TVL_MINTS: usize = 16; // Asset mints tracked in VaultStore.mint_tvls
+const WITHDRAWAL_SETTLEMENT_PERIOD: i64 = 3600; // 1 hour between fulfillment and claim
+const MAX_LOCK_PERIOD: i64 = 31_536_000; // 1 year
+const MAX_EARLY_EXIT_PENALTY_BPS: u16 = 1000; // 10% at the start of the lock
+const INBOUND_NONCE_WINDOW: u64 = 64; // Nonces tracked below the highest one on unordered channels
+const EMERGENCY_INDEX_PRECISION: u128 = 1_000_000_000_000_000_000; // Scale of Vault.emergency_claim_index
+// Current account schema versions; version 0 marks accounts written before versioning
+const VAULT_STORE_VERSION: u8 = 1;
+const VAULT_VERSION: u8 = 1;
+const USER_POSITION_VERSION: u8 = 1;
+const YIELD_TRACKER_VERSION: u8 = 1;
 
 // Cross-chain identifiers for supported networks
 pub mod chain_ids {
@@ -27,6 +70,17 @@ pub mod chain_ids {
     pub const LINEA: u16 = 183;  // New for CCTP
     pub const SONIC: u16 = 185;  // New for CCTP
     pub const WORLD_CHAIN: u16 = 186;  // New for CCTP
+    pub const SOLANA: u16 = 168;
+}
+
+// Pause bits shared by VaultStore.pause_flags and Vault.pause_flags
+pub mod pause_flags {
+    pub const DEPOSITS: u8 = 1 << 0;
+    pub const WITHDRAWALS: u8 = 1 << 1;
+    pub const REBALANCE: u8 = 1 << 2;
+    pub const CROSS_CHAIN_SEND: u8 = 1 << 3;
+    pub const CCTP: u8 = 1 << 4;
+    pub const ALL: u8 = DEPOSITS | WITHDRAWALS | REBALANCE | CROSS_CHAIN_SEND | CCTP;
 }
 
 // CCTP V2 Domain mappings
@@ -52,11 +106,16 @@ pub mod omnivault {
         let vault_store = &mut ctx.accounts.vault_store;
         vault_store.authority = ctx.accounts.authority.key();
         vault_store.total_vaults = 0;
-        vault_store.total_tvl = 0;
+        vault_store.legacy_total_tvl = 0;
         vault_store.fee_rate = 100; // 1% default fee
         vault_store.bump = ctx.bumps.vault_store;
         vault_store.last_global_rebalance = Clock::get()?.unix_timestamp;
         vault_store.emergency_pause = false;
+        vault_store.pending_authority = Pubkey::default();
+        vault_store.config_delay = DEFAULT_CONFIG_DELAY;
+        vault_store.mint_tvls = vec![];
+        vault_store.pause_flags = 0;
+        vault_store.version = VAULT_STORE_VERSION;
         
         // Initialize supported chains for cross-chain operations
         vault_store.supported_chains = vec![
@@ -78,17 +137,31 @@ pub mod omnivault {
         risk_profile: RiskProfile,
         min_deposit: u64,
         target_chains: Vec<u16>,
+        deposit_cap: u64,
+        asset_kind: AssetKind,
     ) -> Result<()> {
         require!(!target_chains.is_empty(), OmniVaultError::InvalidChainConfiguration);
         require!(target_chains.len() <= 10, OmniVaultError::TooManyChains);
         require!(min_deposit > 0, OmniVaultError::InvalidAmount);
+        // SOL vaults are denominated in lamports, so they must be created against the wSOL mint
+        require!(
+            asset_kind == AssetKind::Spl || ctx.accounts.asset_mint.key() == native_mint::ID,
+            OmniVaultError::AssetKindMismatch
+        );
         
         let vault_store = &mut ctx.accounts.vault_store;
+        require!(
+            target_chains.iter().all(|chain| vault_store.supported_chains.contains(chain)),
+            OmniVaultError::UnsupportedChain
+        );
+        vault_store.mint_tvl_mut(ctx.accounts.asset_mint.key())?;
         let vault = &mut ctx.accounts.vault;
         let yield_tracker = &mut ctx.accounts.yield_tracker;
         
         vault.id = vault_store.total_vaults;
         vault.owner = ctx.accounts.owner.key();
+        vault.creator = ctx.accounts.owner.key();
+        vault.pending_owner = Pubkey::default();
         vault.risk_profile = risk_profile.clone();
         vault.total_deposits = 0;
         vault.total_yield = 0;
@@ -101,6 +174,20 @@ pub mod omnivault {
         vault.rebalance_threshold = 200; // 2% threshold
         vault.emergency_exit = false;
         vault.bump = ctx.bumps.vault;
+        vault.share_mint = ctx.accounts.share_mint.key();
+        vault.share_price = PRICE_PRECISION;
+        vault.accrued_fees = 0;
+        vault.management_fee_bps = 0;
+        vault.performance_fee_bps = 0;
+        vault.high_water_mark = PRICE_PRECISION;
+        vault.last_fee_accrual = vault.last_rebalance;
+        vault.deposit_cap = deposit_cap;
+        vault.asset_mint = ctx.accounts.asset_mint.key();
+        vault.token_account = ctx.accounts.vault_token_account.key();
+        vault.extra_assets = vec![];
+        vault.asset_kind = asset_kind;
+        vault.pause_flags = 0;
+        vault.version = VAULT_VERSION;
         
         // Initialize yield tracker
         yield_tracker.vault = vault.key();
@@ -108,6 +195,7 @@ pub mod omnivault {
         yield_tracker.last_update = 0;
         yield_tracker.query_nonce = 0;
         yield_tracker.bump = ctx.bumps.yield_tracker;
+        yield_tracker.version = YIELD_TRACKER_VERSION;
         
         vault_store.total_vaults += 1;
         
@@ -123,32 +211,66 @@ pub mod omnivault {
     }
 
     /// Deposit tokens into a vault with automatic yield optimization trigger
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    pub fn deposit<'info>(ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>, amount: u64) -> Result<()> {
         require!(amount > 0, OmniVaultError::InvalidAmount);
         
         let vault = &mut ctx.accounts.vault;
         let user_position = &mut ctx.accounts.user_position;
-        let vault_store = &ctx.accounts.vault_store;
+        let vault_store = &mut ctx.accounts.vault_store;
         
         require!(amount >= vault.min_deposit, OmniVaultError::DepositTooSmall);
         require!(vault.is_active, OmniVaultError::VaultInactive);
-        require!(!vault_store.emergency_pause, OmniVaultError::SystemPaused);
+        require_not_paused(vault_store, vault, pause_flags::DEPOSITS)?;
         require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
         
+        // Price the deposit against the vault before it changes
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        
         // Transfer tokens from user to vault
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.vault_token_account.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[],
+        )?;
+        
+        // Credit only what arrived, net of any Token-2022 transfer fee
+        ctx.accounts.vault_token_account.reload()?;
+        let amount = ctx.accounts.vault_token_account.amount - balance_before;
+        check_deposit_caps(vault, vault_store, nav_before, amount)?;
+        let shares = shares_for_amount(amount, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        
+        // Mint vault shares to the depositor
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.user_share_account.to_account_info(),
+            authority: vault.to_account_info(),
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, amount)?;
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, shares)?;
         
         // Update vault and user position
         vault.total_deposits += amount;
+        vault.update_share_price(ctx.accounts.share_mint.supply + shares);
+        sync_tvl(vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
         user_position.amount += amount;
-        user_position.last_deposit = Clock::get()?.unix_timestamp;
+        user_position.record_deposit(vault, ctx.accounts.user_share_account.amount, shares, Clock::get()?.unix_timestamp);
         
         // Initialize user position if needed
         if user_position.user == Pubkey::default() {
@@ -156,6 +278,7 @@ pub mod omnivault {
             user_position.vault = vault.key();
             user_position.last_withdrawal = 0;
             user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
         }
         
         emit!(DepositMade {
@@ -163,9 +286,10 @@ pub mod omnivault {
             user: ctx.accounts.user.key(),
             amount,
             new_total: vault.total_deposits,
+            shares,
         });
         
-        msg!("Deposited {} tokens to vault {}", amount, vault.id);
+        msg!("Deposited {} tokens to vault {} for {} shares", amount, vault.id, shares);
         
         // Trigger yield optimization query if significant deposit
         if amount > vault.total_deposits / 10 { // If deposit is >10% of vault
@@ -182,43 +306,53 @@ pub mod omnivault {
         
         let vault = &mut ctx.accounts.vault;
         let user_position = &mut ctx.accounts.user_position;
-        let vault_store = &ctx.accounts.vault_store;
+        let vault_store = &mut ctx.accounts.vault_store;
         
         require!(amount >= vault.min_deposit, OmniVaultError::DepositTooSmall);
         require!(vault.is_active, OmniVaultError::VaultInactive);
-        require!(!vault_store.emergency_pause, OmniVaultError::SystemPaused);
+        require_not_paused(vault_store, vault, pause_flags::DEPOSITS)?;
         require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
         
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        check_deposit_caps(vault, vault_store, nav_before, amount)?;
+        let shares = shares_for_amount(amount, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        
         // Get vault PDA seeds for signing
         let bump = &[vault.bump];
         let vault_seeds = &[
             b"vault".as_ref(),
-            vault.owner.as_ref(),
+            vault.creator.as_ref(),
             &vault.id.to_le_bytes(),
             bump,
         ];
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
 
         // Transfer SOL from user to vault using system program
-        let transfer_instruction = system_instruction::transfer(
-            &ctx.accounts.user.key(),
-            &vault.key(),
-            amount,
-        );
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.user.to_account_info(),
+            to: vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, amount)?;
         
-        anchor_lang::solana_program::program::invoke_signed(
-            &transfer_instruction,
-            &[
-                ctx.accounts.user.to_account_info(),
-                vault.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-            &[vault_seeds],
-        )?;
+        // Mint vault shares to the depositor
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.user_share_account.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, shares)?;
         
         // Update vault and user position
         vault.total_deposits += amount;
+        vault.update_share_price(ctx.accounts.share_mint.supply + shares);
+        sync_tvl(vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
         user_position.amount += amount;
-        user_position.last_deposit = Clock::get()?.unix_timestamp;
+        user_position.record_deposit(vault, ctx.accounts.user_share_account.amount, shares, Clock::get()?.unix_timestamp);
         
         // Initialize user position if needed
         if user_position.user == Pubkey::default() {
@@ -226,6 +360,7 @@ pub mod omnivault {
             user_position.vault = vault.key();
             user_position.last_withdrawal = 0;
             user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
         }
         
         emit!(DepositMade {
@@ -233,233 +368,979 @@ pub mod omnivault {
             user: ctx.accounts.user.key(),
             amount,
             new_total: vault.total_deposits,
+            shares,
         });
         
-        msg!("Deposited {} SOL to vault {}", amount, vault.id);
+        msg!("Deposited {} SOL to vault {} for {} shares", amount, vault.id, shares);
         Ok(())
     }
 
-    /// Withdraw tokens from a vault
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+    /// Deposit native SOL into a wSOL-denominated SPL vault, wrapping it in the vault token account
+    pub fn deposit_wrapped_sol(ctx: Context<DepositWrappedSol>, amount: u64) -> Result<()> {
         require!(amount > 0, OmniVaultError::InvalidAmount);
         
-        let user_position = &mut ctx.accounts.user_position;
         let vault = &mut ctx.accounts.vault;
+        let user_position = &mut ctx.accounts.user_position;
+        let vault_store = &mut ctx.accounts.vault_store;
+        
+        require!(amount >= vault.min_deposit, OmniVaultError::DepositTooSmall);
+        require!(vault.is_active, OmniVaultError::VaultInactive);
+        require_not_paused(vault_store, vault, pause_flags::DEPOSITS)?;
+        require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
         
-        require!(user_position.amount >= amount, OmniVaultError::InsufficientBalance);
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        check_deposit_caps(vault, vault_store, nav_before, amount)?;
+        let shares = shares_for_amount(amount, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        require!(shares > 0, OmniVaultError::InvalidAmount);
         
-        // Calculate withdrawal fee based on vault store fee rate
-        let vault_store = &ctx.accounts.vault_store;
-        let fee = (amount * vault_store.fee_rate as u64) / 10000;
-        let withdrawal_amount = amount - fee;
+        // Wrap the lamports into the vault's wSOL account
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.user.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, amount)?;
+        let cpi_accounts = token_interface::SyncNative {
+            account: ctx.accounts.vault_token_account.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token_interface::sync_native(cpi_ctx)?;
         
-        // Get vault data for seeds
+        // Mint vault shares to the depositor
         let bump = &[vault.bump];
         let vault_seeds = &[
             b"vault".as_ref(),
-            vault.owner.as_ref(),
+            vault.creator.as_ref(),
             &vault.id.to_le_bytes(),
             bump,
         ];
-        
-        // Transfer tokens from vault to user
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.user_share_account.to_account_info(),
             authority: vault.to_account_info(),
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        token::transfer(cpi_ctx, withdrawal_amount)?;
+        token_interface::mint_to(cpi_ctx, shares)?;
         
         // Update vault and user position
-        vault.total_deposits -= amount;
-        user_position.amount -= amount;
-        user_position.last_withdrawal = Clock::get()?.unix_timestamp;
+        vault.total_deposits += amount;
+        vault.update_share_price(ctx.accounts.share_mint.supply + shares);
+        sync_tvl(vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        user_position.amount += amount;
+        user_position.record_deposit(vault, ctx.accounts.user_share_account.amount, shares, Clock::get()?.unix_timestamp);
+        
+        if user_position.user == Pubkey::default() {
+            user_position.user = ctx.accounts.user.key();
+            user_position.vault = vault.key();
+            user_position.last_withdrawal = 0;
+            user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
+        }
+        
+        emit!(DepositMade {
+            vault_id: vault.id,
+            user: ctx.accounts.user.key(),
+            amount,
+            new_total: vault.total_deposits,
+            shares,
+        });
+        
+        msg!("Wrapped and deposited {} lamports to vault {} for {} shares", amount, vault.id, shares);
+        Ok(())
+    }
+
+    /// Withdraw tokens from a vault by burning vault shares
+    pub fn withdraw<'info>(ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>, shares: u64) -> Result<()> {
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        require!(ctx.accounts.user_share_account.amount >= shares, OmniVaultError::InsufficientBalance);
+        
+        let user_position = &mut ctx.accounts.user_position;
+        let vault = &mut ctx.accounts.vault;
+        let vault_store = &mut ctx.accounts.vault_store;
+        
+        // Emergency exits go through emergency_withdraw so every holder is paid pro rata
+        require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
+        require_not_paused(vault_store, vault, pause_flags::WITHDRAWALS)?;
+        
+        // Redeem shares at the current price per share
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        let amount = amount_for_shares(shares, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        
+        // Exits inside the lock window forfeit a decaying penalty to remaining holders
+        let now = Clock::get()?.unix_timestamp;
+        let balance = ctx.accounts.user_share_account.amount;
+        let penalty = user_position.redeem_shares(vault, balance, shares, amount, now)?;
+        
+        // Calculate withdrawal fee based on vault store fee rate
+        let fee = withdrawal_fee(amount - penalty, vault_store.fee_rate)?;
+        let withdrawal_amount = amount - penalty - fee;
+        
+        // Capital deployed on another chain has to come back through the withdrawal queue
+        let available = token_liquidity(vault, ctx.accounts.vault_token_account.amount);
+        if available < withdrawal_amount {
+            msg!("Vault {} holds {} locally (best chain {}), use request_withdrawal", vault.id, available, vault.current_best_chain);
+            return Err(OmniVaultError::InsufficientLiquidity.into());
+        }
+        
+        // Burn the redeemed shares
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.user_share_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token_interface::burn(cpi_ctx, shares)?;
+        
+        // Get vault data for seeds
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        
+        // Transfer tokens from vault to user
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.vault_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            vault.to_account_info(),
+            ctx.remaining_accounts,
+            withdrawal_amount,
+            signer_seeds,
+        )?;
+        
+        // Update vault and user position. Shares are transferable, so the
+        // position's principal can be smaller than what the holder redeems.
+        vault.debit_exit(amount, penalty)?;
+        vault.update_share_price(ctx.accounts.share_mint.supply - shares);
+        vault.accrued_fees += fee;
+        sync_tvl(vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        user_position.amount = user_position.amount.saturating_sub(amount);
+        user_position.last_withdrawal = now;
+        
+        if user_position.user == Pubkey::default() {
+            user_position.user = ctx.accounts.user.key();
+            user_position.vault = vault.key();
+            user_position.last_deposit = 0;
+            user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
+        }
         
         emit!(WithdrawalMade {
             vault_id: vault.id,
             user: ctx.accounts.user.key(),
             amount: withdrawal_amount,
             fee,
+            shares,
+            penalty,
         });
         
-        msg!("Withdrew {} tokens from vault {} (fee: {})", withdrawal_amount, vault.id, fee);
+        msg!("Withdrew {} tokens from vault {} (fee: {}, penalty: {}, shares burned: {})", withdrawal_amount, vault.id, fee, penalty, shares);
         Ok(())
     }
 
-    /// Withdraw native SOL from a vault
-    pub fn withdraw_sol(ctx: Context<WithdrawSol>, amount: u64) -> Result<()> {
-        require!(amount > 0, OmniVaultError::InvalidAmount);
+    /// Withdraw native SOL from a vault by burning vault shares
+    pub fn withdraw_sol(ctx: Context<WithdrawSol>, shares: u64) -> Result<()> {
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        require!(ctx.accounts.user_share_account.amount >= shares, OmniVaultError::InsufficientBalance);
         
         let user_position = &mut ctx.accounts.user_position;
         let vault = &mut ctx.accounts.vault;
+        let vault_store = &mut ctx.accounts.vault_store;
+        
+        // Emergency exits go through emergency_withdraw so every holder is paid pro rata
+        require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
+        require_not_paused(vault_store, vault, pause_flags::WITHDRAWALS)?;
+        
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        let amount = amount_for_shares(shares, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        require!(amount > 0, OmniVaultError::InvalidAmount);
         
-        require!(user_position.amount >= amount, OmniVaultError::InsufficientBalance);
+        // Exits inside the lock window forfeit a decaying penalty to remaining holders
+        let now = Clock::get()?.unix_timestamp;
+        let balance = ctx.accounts.user_share_account.amount;
+        let penalty = user_position.redeem_shares(vault, balance, shares, amount, now)?;
         
         // Calculate withdrawal fee
-        let vault_store = &ctx.accounts.vault_store;
-        let fee = (amount * vault_store.fee_rate as u64) / 10000;
-        let withdrawal_amount = amount - fee;
+        let fee = withdrawal_fee(amount - penalty, vault_store.fee_rate)?;
+        let withdrawal_amount = amount - penalty - fee;
+        
+        // Pay out only lamports above rent, accrued fees and emergency reserves
+        let available = sol_liquidity(&vault.to_account_info(), vault)?;
+        if available < withdrawal_amount {
+            msg!("Vault {} holds {} lamports free (best chain {}), use request_withdrawal", vault.id, available, vault.current_best_chain);
+            return Err(OmniVaultError::InsufficientLiquidity.into());
+        }
+        
+        // Burn the redeemed shares
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.user_share_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token_interface::burn(cpi_ctx, shares)?;
         
         // Transfer SOL from vault to user
         **vault.to_account_info().try_borrow_mut_lamports()? -= withdrawal_amount;
         **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += withdrawal_amount;
         
         // Update vault and user position
-        vault.total_deposits -= amount;
-        user_position.amount -= amount;
-        user_position.last_withdrawal = Clock::get()?.unix_timestamp;
+        vault.debit_exit(amount, penalty)?;
+        vault.update_share_price(ctx.accounts.share_mint.supply - shares);
+        vault.accrued_fees += fee;
+        sync_tvl(vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        user_position.amount = user_position.amount.saturating_sub(amount);
+        user_position.last_withdrawal = now;
+        
+        if user_position.user == Pubkey::default() {
+            user_position.user = ctx.accounts.user.key();
+            user_position.vault = vault.key();
+            user_position.last_deposit = 0;
+            user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
+        }
         
         emit!(WithdrawalMade {
             vault_id: vault.id,
             user: ctx.accounts.user.key(),
             amount: withdrawal_amount,
             fee,
+            shares,
+            penalty,
         });
         
-        msg!("Withdrew {} SOL from vault {} (fee: {})", withdrawal_amount, vault.id, fee);
+        msg!("Withdrew {} SOL from vault {} (fee: {}, penalty: {}, shares burned: {})", withdrawal_amount, vault.id, fee, penalty, shares);
         Ok(())
     }
 
-    /// Send cross-chain yield query via LayerZero
-    pub fn query_cross_chain_yields(
-        ctx: Context<QueryCrossChainYields>,
-        target_chains: Vec<u16>,
-    ) -> Result<()> {
-        let vault = &ctx.accounts.vault;
-        let yield_tracker = &mut ctx.accounts.yield_tracker;
+    /// Exit a vault in emergency mode: pays a pro-rata share of on-hand liquidity with fees
+    /// waived and records the unpaid remainder as an emergency claim
+    pub fn emergency_withdraw<'info>(ctx: Context<'_, '_, 'info, 'info, EmergencyWithdraw<'info>>, shares: u64) -> Result<()> {
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        require!(ctx.accounts.user_share_account.amount >= shares, OmniVaultError::InsufficientBalance);
         
-        require!(!target_chains.is_empty(), OmniVaultError::InvalidChainConfiguration);
-        require!(target_chains.len() <= MAX_CROSS_CHAIN_QUERIES as usize, OmniVaultError::TooManyChains);
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.emergency_exit, OmniVaultError::VaultNotInEmergencyExit);
+        require_not_paused(&ctx.accounts.vault_store, vault, pause_flags::WITHDRAWALS)?;
         
-        let clock = Clock::get()?;
+        // No fee accrual, withdrawal fee or early-exit penalty in emergency mode
+        let nav_before = vault.total_assets();
+        let amount = amount_for_shares(shares, ctx.accounts.share_mint.supply, nav_before)?;
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        let liquidity = token_liquidity(vault, ctx.accounts.vault_token_account.amount);
+        vault.sync_emergency_liquidity(liquidity)?;
+        let paid = vault.emergency_exit_payout(amount)?;
+        let remainder = amount - paid;
         
-        // Rate limiting: Don't query too frequently
-        require!(
-            clock.unix_timestamp - yield_tracker.last_update > 300, // 5 minutes
-            OmniVaultError::QueryTooFrequent
-        );
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.user_share_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token_interface::burn(cpi_ctx, shares)?;
         
-        yield_tracker.query_nonce += 1;
+        if paid > 0 {
+            let bump = &[vault.bump];
+            let vault_seeds = &[
+                b"vault".as_ref(),
+                vault.creator.as_ref(),
+                &vault.id.to_le_bytes(),
+                bump,
+            ];
+            let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                ctx.accounts.vault_token_account.to_account_info(),
+                &ctx.accounts.asset_mint,
+                ctx.accounts.user_token_account.to_account_info(),
+                vault.to_account_info(),
+                ctx.remaining_accounts,
+                paid,
+                signer_seeds,
+            )?;
+        }
         
-        // Create cross-chain query message
-        let query_message = CrossChainMessage {
-            action: CrossChainAction::YieldQuery {
-                vault_id: vault.id,
-                risk_profile: vault.risk_profile.clone(),
-                query_nonce: yield_tracker.query_nonce,
-                requested_chains: target_chains.clone(),
-            },
-            timestamp: clock.unix_timestamp,
-            nonce: yield_tracker.query_nonce,
+        vault.debit_assets(amount)?;
+        vault.update_share_price(ctx.accounts.share_mint.supply - shares);
+        sync_tvl(&mut ctx.accounts.vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        
+        let emergency_claim = &mut ctx.accounts.emergency_claim;
+        if emergency_claim.user == Pubkey::default() {
+            emergency_claim.vault = vault.key();
+            emergency_claim.user = ctx.accounts.user.key();
+            emergency_claim.bump = ctx.bumps.emergency_claim;
+        }
+        emergency_claim.settle(vault);
+        emergency_claim.add(vault, remainder)?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        let user_position = &mut ctx.accounts.user_position;
+        if user_position.user == Pubkey::default() {
+            user_position.user = ctx.accounts.user.key();
+            user_position.vault = vault.key();
+            user_position.last_deposit = 0;
+            user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
+        }
+        user_position.release_shares(vault, ctx.accounts.user_share_account.amount, shares, now);
+        user_position.amount = user_position.amount.saturating_sub(amount);
+        user_position.last_withdrawal = now;
+        
+        emit!(EmergencyWithdrawal {
+            vault_id: vault.id,
+            user: ctx.accounts.user.key(),
+            shares,
+            paid,
+            claimed: remainder,
+        });
+        
+        msg!("Emergency withdrawal from vault {}: paid {}, {} recorded as a claim", vault.id, paid, remainder);
+        Ok(())
+    }
+
+    /// Exit a native SOL vault in emergency mode, paying pro rata from the vault's lamports
+    pub fn emergency_withdraw_sol(ctx: Context<EmergencyWithdrawSol>, shares: u64) -> Result<()> {
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        require!(ctx.accounts.user_share_account.amount >= shares, OmniVaultError::InsufficientBalance);
+        
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.emergency_exit, OmniVaultError::VaultNotInEmergencyExit);
+        require_not_paused(&ctx.accounts.vault_store, vault, pause_flags::WITHDRAWALS)?;
+        
+        let nav_before = vault.total_assets();
+        let amount = amount_for_shares(shares, ctx.accounts.share_mint.supply, nav_before)?;
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        let liquidity = sol_liquidity(&vault.to_account_info(), vault)?;
+        vault.sync_emergency_liquidity(liquidity)?;
+        let paid = vault.emergency_exit_payout(amount)?;
+        let remainder = amount - paid;
+        
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.user_share_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
         };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token_interface::burn(cpi_ctx, shares)?;
         
-        let message_data = query_message.try_to_vec()?;
+        **vault.to_account_info().try_borrow_mut_lamports()? -= paid;
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += paid;
         
-        // Enhanced LayerZero V2 messaging
-        for chain_id in &target_chains {
-            // Create LayerZero send options with gas settings
-            let options = create_lz_options(*chain_id)?;
-            
-            let lz_instruction_data = LzSendData {
-                dst_chain_id: *chain_id,
-                message: message_data.clone(),
-                options,
-            };
-            
-            let serialized_data = lz_instruction_data.try_to_vec()?;
-            
-            // Create instruction for LayerZero endpoint
-            let lz_instruction = Instruction {
-                program_id: Pubkey::from_str(LAYERZERO_ENDPOINT).unwrap(),
-                accounts: vec![
-                    AccountMeta::new(ctx.accounts.endpoint.key(), false),
-                    AccountMeta::new(ctx.accounts.oapp_config.key(), false),
-                    AccountMeta::new(ctx.accounts.payer.key(), true),
-                    AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
-                ],
-                data: serialized_data,
-            };
-            
-            // Invoke LayerZero endpoint with proper account handling
-            invoke_signed(
-                &lz_instruction,
-                &[
-                    ctx.accounts.endpoint.to_account_info(),
-                    ctx.accounts.oapp_config.to_account_info(),
-                    ctx.accounts.payer.to_account_info(),
-                    ctx.accounts.system_program.to_account_info(),
-                ],
-                &[],
-            )?;
-            
-            msg!("Enhanced yield query sent to chain {} with nonce {}", chain_id, yield_tracker.query_nonce);
+        vault.debit_assets(amount)?;
+        vault.update_share_price(ctx.accounts.share_mint.supply - shares);
+        sync_tvl(&mut ctx.accounts.vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        
+        let emergency_claim = &mut ctx.accounts.emergency_claim;
+        if emergency_claim.user == Pubkey::default() {
+            emergency_claim.vault = vault.key();
+            emergency_claim.user = ctx.accounts.user.key();
+            emergency_claim.bump = ctx.bumps.emergency_claim;
         }
+        emergency_claim.settle(vault);
+        emergency_claim.add(vault, remainder)?;
         
-        yield_tracker.last_update = clock.unix_timestamp;
+        let now = Clock::get()?.unix_timestamp;
+        let user_position = &mut ctx.accounts.user_position;
+        if user_position.user == Pubkey::default() {
+            user_position.user = ctx.accounts.user.key();
+            user_position.vault = vault.key();
+            user_position.last_deposit = 0;
+            user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
+        }
+        user_position.release_shares(vault, ctx.accounts.user_share_account.amount, shares, now);
+        user_position.amount = user_position.amount.saturating_sub(amount);
+        user_position.last_withdrawal = now;
         
-        emit!(YieldQuerySent {
+        emit!(EmergencyWithdrawal {
             vault_id: vault.id,
-            chains: target_chains,
-            nonce: yield_tracker.query_nonce,
+            user: ctx.accounts.user.key(),
+            shares,
+            paid,
+            claimed: remainder,
         });
         
+        msg!("Emergency withdrawal from vault {}: paid {} lamports, {} recorded as a claim", vault.id, paid, remainder);
         Ok(())
     }
 
-    /// Receive cross-chain yield data and trigger rebalancing if needed
-    pub fn lz_receive(
-        ctx: Context<LzReceive>,
-        src_chain_id: u16,
-        payload: Vec<u8>,
-    ) -> Result<()> {
-        require!(!payload.is_empty(), OmniVaultError::InvalidPayload);
+    /// Redeem the part of an emergency claim covered by liquidity recovered since its last
+    /// redemption, pro rata with remaining holders. Outside emergency mode the rest of the
+    /// claim is paid at par from free liquidity.
+    pub fn redeem_emergency_claim<'info>(ctx: Context<'_, '_, 'info, 'info, RedeemEmergencyClaim<'info>>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let emergency_claim = &mut ctx.accounts.emergency_claim;
+        require_not_paused(&ctx.accounts.vault_store, vault, pause_flags::WITHDRAWALS)?;
         
-        // Validate LayerZero endpoint
-        require!(
-            ctx.accounts.endpoint.key() == Pubkey::from_str(LAYERZERO_ENDPOINT).unwrap(),
-            OmniVaultError::InvalidLayerZeroEndpoint
-        );
+        let liquidity = token_liquidity(vault, ctx.accounts.vault_token_account.amount);
+        let paid = emergency_redemption(vault, emergency_claim, liquidity)?;
+        require!(paid > 0, OmniVaultError::NothingToRedeem);
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.vault_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            vault.to_account_info(),
+            ctx.remaining_accounts,
+            paid,
+            signer_seeds,
+        )?;
+        
+        emit!(EmergencyClaimRedeemed {
+            vault_id: vault.id,
+            user: emergency_claim.user,
+            amount: paid,
+            remaining: emergency_claim.amount,
+        });
         
+        msg!("Redeemed {} of emergency claim on vault {} ({} remaining)", paid, vault.id, emergency_claim.amount);
+        Ok(())
+    }
+
+    /// Redeem part of an emergency claim on a native SOL vault
+    pub fn redeem_emergency_claim_sol(ctx: Context<RedeemEmergencyClaimSol>) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
-        let yield_tracker = &mut ctx.accounts.yield_tracker;
+        let emergency_claim = &mut ctx.accounts.emergency_claim;
+        require_not_paused(&ctx.accounts.vault_store, vault, pause_flags::WITHDRAWALS)?;
         
-        // Deserialize cross-chain message
-        let message: CrossChainMessage = CrossChainMessage::try_from_slice(&payload)?;
+        let liquidity = sol_liquidity(&vault.to_account_info(), vault)?;
+        let paid = emergency_redemption(vault, emergency_claim, liquidity)?;
+        require!(paid > 0, OmniVaultError::NothingToRedeem);
         
-        match message.action {
-            CrossChainAction::YieldResponse {
-                vault_id,
-                chain_id,
-                apy,
-                tvl,
-                risk_score,
-                query_nonce,
-            } => {
-                require!(vault_id == vault.id, OmniVaultError::InvalidVaultId);
-                require!(query_nonce == yield_tracker.query_nonce, OmniVaultError::InvalidNonce);
-                
-                let new_yield = ChainYield {
-                    chain_id,
-                    apy,
-                    tvl,
-                    risk_score,
-                    last_updated: Clock::get()?.unix_timestamp,
-                };
-                
-                // Update or add chain yield data
-                if let Some(existing_yield) = yield_tracker.chain_yields.iter_mut().find(|cy| cy.chain_id == chain_id) {
-                    *existing_yield = new_yield;
-                } else {
-                    yield_tracker.chain_yields.push(new_yield);
-                }
-                
-                // Check if rebalancing is needed
-                if let Some(best_chain) = find_best_chain(&yield_tracker.chain_yields, &vault.risk_profile) {
-                    let current_apy = vault.current_apy;
-                    let improvement = best_chain.apy.saturating_sub(current_apy);
+        **vault.to_account_info().try_borrow_mut_lamports()? -= paid;
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += paid;
+        
+        emit!(EmergencyClaimRedeemed {
+            vault_id: vault.id,
+            user: emergency_claim.user,
+            amount: paid,
+            remaining: emergency_claim.amount,
+        });
+        
+        msg!("Redeemed {} lamports of emergency claim on vault {} ({} remaining)", paid, vault.id, emergency_claim.amount);
+        Ok(())
+    }
+
+    /// Queue a withdrawal for capital deployed off Solana, escrowing the shares until fulfillment
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        require!(ctx.accounts.user_share_account.amount >= shares, OmniVaultError::InsufficientBalance);
+        require!(!ctx.accounts.vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
+        require_not_paused(&ctx.accounts.vault_store, &ctx.accounts.vault, pause_flags::WITHDRAWALS)?;
+        // Queued exits are priced later, so they cannot carry a penalty and must wait out the lock
+        let now = Clock::get()?.unix_timestamp;
+        let balance = ctx.accounts.user_share_account.amount;
+        let user_position = &mut ctx.accounts.user_position;
+        let (locked, received) = user_position.locked_split(&ctx.accounts.vault, balance, shares, now);
+        require!(locked == 0 && received == 0, OmniVaultError::PositionLocked);
+        user_position.release_shares(&ctx.accounts.vault, balance, shares, now);
+        
+        if user_position.user == Pubkey::default() {
+            user_position.user = ctx.accounts.user.key();
+            user_position.vault = ctx.accounts.vault.key();
+            user_position.last_deposit = 0;
+            user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
+        }
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.user_share_account.to_account_info(),
+            &ctx.accounts.share_mint,
+            ctx.accounts.share_escrow.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            &[],
+            shares,
+            &[],
+        )?;
+        
+        let vault = &mut ctx.accounts.vault;
+        let withdrawal_request = &mut ctx.accounts.withdrawal_request;
+        
+        withdrawal_request.vault = vault.key();
+        withdrawal_request.user = ctx.accounts.user.key();
+        withdrawal_request.index = vault.withdrawal_queue_tail;
+        withdrawal_request.shares = shares;
+        withdrawal_request.amount = 0;
+        withdrawal_request.fee = 0;
+        withdrawal_request.status = WithdrawalStatus::Pending;
+        withdrawal_request.requested_at = now;
+        withdrawal_request.claimable_at = 0;
+        withdrawal_request.bump = ctx.bumps.withdrawal_request;
+        vault.withdrawal_queue_tail += 1;
+        
+        let queue_position = withdrawal_request.index - vault.withdrawal_queue_head;
+        emit!(WithdrawalRequested {
+            vault_id: vault.id,
+            user: withdrawal_request.user,
+            index: withdrawal_request.index,
+            shares,
+            queue_position,
+        });
+        
+        msg!("Withdrawal request {} queued for vault {} at position {}", withdrawal_request.index, vault.id, queue_position);
+        Ok(())
+    }
+
+    /// Settle the request at the head of a vault's withdrawal queue once funds are back (keeper only).
+    /// A head request larger than local liquidity is filled in part and stays at the head;
+    /// a cancelled head request is skipped.
+    pub fn fulfill_withdrawal(ctx: Context<FulfillWithdrawal>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let vault_store = &mut ctx.accounts.vault_store;
+        let withdrawal_request = &mut ctx.accounts.withdrawal_request;
+        
+        require!(withdrawal_request.index == vault.withdrawal_queue_head, OmniVaultError::WithdrawalOutOfOrder);
+        if withdrawal_request.status == WithdrawalStatus::Cancelled {
+            vault.withdrawal_queue_head += 1;
+            msg!("Skipped cancelled withdrawal request {} for vault {}", withdrawal_request.index, vault.id);
+            return Ok(());
+        }
+        require!(withdrawal_request.status == WithdrawalStatus::Pending, OmniVaultError::InvalidWithdrawalStatus);
+        require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
+        require_not_paused(vault_store, vault, pause_flags::WITHDRAWALS)?;
+        
+        // Price the escrowed shares at the current NAV, filling what local liquidity covers
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        let full_amount = amount_for_shares(withdrawal_request.shares, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        let available = token_liquidity(vault, ctx.accounts.vault_token_account.amount);
+        let shares = fillable_shares(
+            withdrawal_request.shares,
+            full_amount - withdrawal_fee(full_amount, vault_store.fee_rate)?,
+            available,
+        );
+        require!(shares > 0, OmniVaultError::InsufficientLiquidity);
+        let amount = amount_for_shares(shares, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        let fee = withdrawal_fee(amount, vault_store.fee_rate)?;
+        let withdrawal_amount = amount - fee;
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.share_escrow.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::burn(cpi_ctx, shares)?;
+        
+        vault.debit_assets(amount)?;
+        vault.update_share_price(ctx.accounts.share_mint.supply - shares);
+        vault.accrued_fees += fee;
+        vault.reserved_withdrawals += withdrawal_amount;
+        sync_tvl(vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        withdrawal_request.shares -= shares;
+        withdrawal_request.amount += withdrawal_amount;
+        withdrawal_request.fee += fee;
+        withdrawal_request.claimable_at = now + WITHDRAWAL_SETTLEMENT_PERIOD;
+        if withdrawal_request.shares == 0 {
+            withdrawal_request.status = WithdrawalStatus::Fulfilled;
+            vault.withdrawal_queue_head += 1;
+        }
+        
+        emit!(WithdrawalFulfilled {
+            vault_id: vault.id,
+            user: withdrawal_request.user,
+            index: withdrawal_request.index,
+            amount: withdrawal_amount,
+            fee,
+            claimable_at: withdrawal_request.claimable_at,
+            remaining_shares: withdrawal_request.shares,
+        });
+        
+        msg!("Withdrawal request {} for vault {} fulfilled: {} claimable at {}", withdrawal_request.index, vault.id, withdrawal_amount, withdrawal_request.claimable_at);
+        Ok(())
+    }
+
+    /// Pay out the fulfilled part of a withdrawal request after its settlement period.
+    /// The request account is closed once the queue has moved past it.
+    pub fn claim_withdrawal<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimWithdrawal<'info>>) -> Result<()> {
+        let withdrawal_request = &mut ctx.accounts.withdrawal_request;
+        let vault = &mut ctx.accounts.vault;
+        let amount = withdrawal_request.amount;
+        let index = withdrawal_request.index;
+        let settled = withdrawal_request.status != WithdrawalStatus::Pending
+            && index < vault.withdrawal_queue_head;
+        require!(amount > 0 || settled, OmniVaultError::InvalidWithdrawalStatus);
+        
+        if amount > 0 {
+            require_not_paused(&ctx.accounts.vault_store, vault, pause_flags::WITHDRAWALS)?;
+            require!(
+                Clock::get()?.unix_timestamp >= withdrawal_request.claimable_at,
+                OmniVaultError::SettlementPending
+            );
+            
+            let bump = &[vault.bump];
+            let vault_seeds = &[
+                b"vault".as_ref(),
+                vault.creator.as_ref(),
+                &vault.id.to_le_bytes(),
+                bump,
+            ];
+            let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                ctx.accounts.vault_token_account.to_account_info(),
+                &ctx.accounts.asset_mint,
+                ctx.accounts.user_token_account.to_account_info(),
+                vault.to_account_info(),
+                ctx.remaining_accounts,
+                amount,
+                signer_seeds,
+            )?;
+            vault.reserved_withdrawals -= amount;
+            
+            let user_position = &mut ctx.accounts.user_position;
+            user_position.amount = user_position.amount.saturating_sub(amount + withdrawal_request.fee);
+            user_position.last_withdrawal = Clock::get()?.unix_timestamp;
+            if user_position.user == Pubkey::default() {
+                user_position.user = ctx.accounts.user.key();
+                user_position.vault = vault.key();
+                user_position.last_deposit = 0;
+                user_position.bump = ctx.bumps.user_position;
+                user_position.version = USER_POSITION_VERSION;
+            }
+            withdrawal_request.amount = 0;
+            withdrawal_request.fee = 0;
+            
+            emit!(WithdrawalClaimed {
+                vault_id: vault.id,
+                user: ctx.accounts.user.key(),
+                index,
+                amount,
+            });
+        }
+        
+        if settled {
+            withdrawal_request.close(ctx.accounts.user.to_account_info())?;
+        }
+        
+        msg!("Withdrawal request {} claimed from vault {}: {}", index, vault.id, amount);
+        Ok(())
+    }
+
+    /// Return queued shares that have not been fulfilled yet to their owner.
+    /// Also available in emergency mode, where the shares can then go through emergency_withdraw.
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let withdrawal_request = &mut ctx.accounts.withdrawal_request;
+        require!(withdrawal_request.status == WithdrawalStatus::Pending, OmniVaultError::InvalidWithdrawalStatus);
+        let shares = withdrawal_request.shares;
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.share_escrow.to_account_info(),
+            &ctx.accounts.share_mint,
+            ctx.accounts.user_share_account.to_account_info(),
+            vault.to_account_info(),
+            &[],
+            shares,
+            signer_seeds,
+        )?;
+        
+        // Escrow required the shares to be unlocked, so they come back as the position's own
+        ctx.accounts.user_position.shares += shares;
+        
+        // A cancelled request at the head is passed over right away, later ones when the keeper reaches them
+        withdrawal_request.shares = 0;
+        withdrawal_request.status = WithdrawalStatus::Cancelled;
+        if withdrawal_request.index == vault.withdrawal_queue_head {
+            vault.withdrawal_queue_head += 1;
+        }
+        
+        emit!(WithdrawalCancelled {
+            vault_id: vault.id,
+            user: withdrawal_request.user,
+            index: withdrawal_request.index,
+            shares,
+        });
+        
+        msg!("Withdrawal request {} for vault {} cancelled, {} shares returned", withdrawal_request.index, vault.id, shares);
+        Ok(())
+    }
+
+    /// Return realized strategy yield to the vault, raising the price per share
+    pub fn harvest_yield<'info>(ctx: Context<'_, '_, 'info, 'info, HarvestYield<'info>>, amount: u64) -> Result<()> {
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        
+        let vault = &mut ctx.accounts.vault;
+        let nav_before = vault.total_assets();
+        
+        // Transfer realized yield into the vault
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.harvester_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.harvester.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[],
+        )?;
+        ctx.accounts.vault_token_account.reload()?;
+        let amount = ctx.accounts.vault_token_account.amount - balance_before;
+        
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        vault.total_yield += amount;
+        vault.update_share_price(ctx.accounts.share_mint.supply);
+        // Charge the performance fee on the harvested yield right away
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        sync_tvl(&mut ctx.accounts.vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        
+        emit!(YieldHarvested {
+            vault_id: vault.id,
+            amount,
+            total_yield: vault.total_yield,
+            share_price: vault.share_price,
+        });
+        
+        msg!("Harvested {} tokens of yield into vault {} (share price: {})", amount, vault.id, vault.share_price);
+        Ok(())
+    }
+
+    /// Return realized strategy yield in native SOL to the vault
+    pub fn harvest_yield_sol(ctx: Context<HarvestYieldSol>, amount: u64) -> Result<()> {
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        
+        let vault = &mut ctx.accounts.vault;
+        let nav_before = vault.total_assets();
+        
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.harvester.to_account_info(),
+            to: vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, amount)?;
+        
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        vault.total_yield += amount;
+        vault.update_share_price(ctx.accounts.share_mint.supply);
+        // Charge the performance fee on the harvested yield right away
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        sync_tvl(&mut ctx.accounts.vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        
+        emit!(YieldHarvested {
+            vault_id: vault.id,
+            amount,
+            total_yield: vault.total_yield,
+            share_price: vault.share_price,
+        });
+        
+        msg!("Harvested {} SOL of yield into vault {} (share price: {})", amount, vault.id, vault.share_price);
+        Ok(())
+    }
+
+    /// Quote the total native fee query_cross_chain_yields would pay for these chains
+    ///
+    /// Expects, for each target chain in order, its ChainConfig and Peer followed by the
+    /// endpoint's quote accounts as remaining accounts. Send accounts may follow, as laid out
+    /// for query_cross_chain_yields, and are ignored. The fee in lamports is returned through
+    /// return data.
+    pub fn quote_cross_chain_query<'info>(
+        ctx: Context<'_, '_, 'info, 'info, QuoteCrossChainQuery<'info>>,
+        target_chains: Vec<u16>,
+    ) -> Result<u64> {
+        let vault = &ctx.accounts.vault;
+        
+        require!(!target_chains.is_empty(), OmniVaultError::InvalidChainConfiguration);
+        require!(target_chains.len() <= MAX_CROSS_CHAIN_QUERIES as usize, OmniVaultError::TooManyChains);
+        
+        // Quote the message the next query would carry
+        let query_nonce = ctx.accounts.yield_tracker.query_nonce + 1;
+        let message_data = yield_query_message(vault, query_nonce, &target_chains, Clock::get()?.unix_timestamp)
+            .try_to_vec()?;
+        let chain_sends = load_chain_sends(&target_chains, ctx.accounts.vault_store.key(), ctx.remaining_accounts)?;
+        
+        let mut total_native_fee: u64 = 0;
+        for chain_send in &chain_sends {
+            let native_fee = quote_lz_fee(&ctx.accounts.endpoint, ctx.accounts.vault_store.key(), chain_send, &message_data)?;
+            total_native_fee = total_native_fee.checked_add(native_fee).ok_or(OmniVaultError::MathOverflow)?;
+        }
+        
+        msg!("Yield query to {} chains quoted at {} lamports", target_chains.len(), total_native_fee);
+        Ok(total_native_fee)
+    }
+
+    /// Send cross-chain yield query via LayerZero
+    ///
+    /// Expects, for each target chain in order, its ChainConfig, Peer and the endpoint's quote
+    /// accounts, then the endpoint's send accounts starting with the vault store, which signs
+    /// as the OApp. Each chain is sent the message it was quoted for, paying the quoted fee.
+    /// Aborts before sending if the quoted native fee exceeds `max_native_fee`, and fails if
+    /// the payer ends up spending more than that.
+    pub fn query_cross_chain_yields<'info>(
+        ctx: Context<'_, '_, 'info, 'info, QueryCrossChainYields<'info>>,
+        target_chains: Vec<u16>,
+        max_native_fee: u64,
+    ) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let yield_tracker = &mut ctx.accounts.yield_tracker;
+        
+        require_not_paused(&ctx.accounts.vault_store, vault, pause_flags::CROSS_CHAIN_SEND)?;
+        require!(!target_chains.is_empty(), OmniVaultError::InvalidChainConfiguration);
+        require!(target_chains.len() <= MAX_CROSS_CHAIN_QUERIES as usize, OmniVaultError::TooManyChains);
+        
+        let clock = Clock::get()?;
+        
+        // Rate limiting: Don't query too frequently
+        require!(
+            clock.unix_timestamp - yield_tracker.last_update > 300, // 5 minutes
+            OmniVaultError::QueryTooFrequent
+        );
+        
+        yield_tracker.query_nonce += 1;
+        
+        // Create cross-chain query message
+        let query_message = yield_query_message(vault, yield_tracker.query_nonce, &target_chains, clock.unix_timestamp);
+        let message_data = query_message.try_to_vec()?;
+        let chain_sends = load_chain_sends(&target_chains, ctx.accounts.vault_store.key(), ctx.remaining_accounts)?;
+        
+        // Quote every message up front so nothing is sent when the total is over budget
+        let mut native_fees = Vec::with_capacity(chain_sends.len());
+        let mut total_native_fee: u64 = 0;
+        for chain_send in &chain_sends {
+            let native_fee = quote_lz_fee(&ctx.accounts.endpoint, ctx.accounts.vault_store.key(), chain_send, &message_data)?;
+            total_native_fee = total_native_fee.checked_add(native_fee).ok_or(OmniVaultError::MathOverflow)?;
+            native_fees.push(native_fee);
+        }
+        require!(total_native_fee <= max_native_fee, OmniVaultError::MessagingFeeTooHigh);
+        
+        let payer_lamports_before = ctx.accounts.payer.lamports();
+        for (chain_send, native_fee) in chain_sends.iter().zip(native_fees) {
+            send_lz_message(&ctx.accounts.endpoint, &ctx.accounts.vault_store, chain_send, &message_data, native_fee)?;
+            msg!(
+                "Yield query sent to chain {} (eid {}) with nonce {}",
+                chain_send.chain_id,
+                chain_send.eid,
+                yield_tracker.query_nonce
+            );
+        }
+        
+        // The quote is advisory; what the endpoint actually charged the payer is what counts
+        let native_fee = native_fee_spent(payer_lamports_before, ctx.accounts.payer.lamports(), max_native_fee)?;
+        
+        yield_tracker.last_update = clock.unix_timestamp;
+        
+        emit!(YieldQuerySent {
+            vault_id: vault.id,
+            chains: target_chains,
+            nonce: yield_tracker.query_nonce,
+            native_fee,
+        });
+        
+        Ok(())
+    }
+
+    /// Receive cross-chain yield data and trigger rebalancing if needed. Called by the
+    /// LayerZero executor through a CPI; the message is only accepted once the endpoint clears it.
+    pub fn lz_receive<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LzReceive<'info>>,
+        params: LzReceiveParams,
+    ) -> Result<()> {
+        let src_chain_id = u16::try_from(params.src_eid).map_err(|_| OmniVaultError::InvalidSourceChain)?;
+        require!(!params.message.is_empty(), OmniVaultError::InvalidPayload);
+        require_invoked_by_cpi(&ctx.accounts.instructions)?;
+        
+        // Clear the payload with the endpoint before acting on it. The endpoint only clears a
+        // payload it verified for this OApp, and clears it once.
+        clear_lz_payload(
+            &ctx.accounts.endpoint,
+            &ctx.accounts.vault_store,
+            ctx.remaining_accounts,
+            &params,
+        )?;
+        
+        let vault = &mut ctx.accounts.vault;
+        let yield_tracker = &mut ctx.accounts.yield_tracker;
+        
+        // Deserialize cross-chain message
+        let message: CrossChainMessage = CrossChainMessage::try_from_slice(&params.message)?;
+        
+        // Each endpoint nonce is accepted once per (vault, source chain). The endpoint numbers
+        // messages per path, and every vault shares the vault store's path, so a single vault
+        // sees gaps unless it is the only one receiving from that chain: default to unordered.
+        let inbound_nonce = &mut ctx.accounts.inbound_nonce;
+        if inbound_nonce.vault == Pubkey::default() {
+            inbound_nonce.vault = vault.key();
+            inbound_nonce.src_chain_id = src_chain_id;
+            inbound_nonce.ordering = NonceOrdering::Unordered;
+            inbound_nonce.bump = ctx.bumps.inbound_nonce;
+        }
+        inbound_nonce.consume(params.nonce)?;
+        
+        match message.action {
+            CrossChainAction::YieldResponse {
+                vault_id,
+                vault: target_vault,
+                chain_id,
+                apy,
+                tvl,
+                risk_score,
+                query_nonce,
+            } => {
+                require!(vault_id == vault.id && target_vault == vault.key(), OmniVaultError::InvalidVaultId);
+                require!(chain_id == src_chain_id, OmniVaultError::InvalidSourceChain);
+                require!(query_nonce == yield_tracker.query_nonce, OmniVaultError::InvalidNonce);
+                
+                let new_yield = ChainYield {
+                    chain_id,
+                    apy,
+                    tvl,
+                    risk_score,
+                    last_updated: Clock::get()?.unix_timestamp,
+                };
+                
+                // Update or add chain yield data
+                if let Some(existing_yield) = yield_tracker.chain_yields.iter_mut().find(|cy| cy.chain_id == chain_id) {
+                    *existing_yield = new_yield;
+                } else {
+                    yield_tracker.chain_yields.push(new_yield);
+                }
+                
+                // Check if rebalancing is needed
+                if let Some(best_chain) = find_best_chain(&yield_tracker.chain_yields, &vault.risk_profile) {
+                    let current_apy = vault.current_apy;
+                    let improvement = best_chain.apy.saturating_sub(current_apy);
                     
                     if improvement > vault.rebalance_threshold && best_chain.chain_id != vault.current_best_chain {
                         // Trigger rebalancing
@@ -488,9 +1369,9 @@ pub mod omnivault {
                 
                 msg!("Received yield data from chain {}: APY {}, TVL {}", chain_id, apy, tvl);
             }
-            CrossChainAction::EmergencyPause { vault_id } => {
-                require!(vault_id == vault.id, OmniVaultError::InvalidVaultId);
-                vault.emergency_exit = true;
+            CrossChainAction::EmergencyPause { vault_id, vault: target_vault } => {
+                require!(vault_id == vault.id && target_vault == vault.key(), OmniVaultError::InvalidVaultId);
+                vault.enter_emergency();
                 
                 emit!(EmergencyPauseActivated {
                     vault_id,
@@ -518,13 +1399,100 @@ pub mod omnivault {
         Ok(())
     }
 
+    /// Register the trusted remote OApp address for a source chain (admin only)
+    pub fn set_peer(ctx: Context<SetPeer>, chain_id: u16, address: [u8; 32]) -> Result<()> {
+        require!(
+            ctx.accounts.vault_store.supported_chains.contains(&chain_id),
+            OmniVaultError::UnsupportedChain
+        );
+        require!(address != [0u8; 32], OmniVaultError::InvalidPeer);
+        
+        let peer = &mut ctx.accounts.peer;
+        peer.chain_id = chain_id;
+        peer.address = address;
+        peer.bump = ctx.bumps.peer;
+        
+        emit!(PeerSet {
+            chain_id,
+            address,
+            set_by: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Peer for chain {} set to {:?}", chain_id, address);
+        Ok(())
+    }
+
+    /// Set the LayerZero endpoint id and executor options used for messages to a chain (admin only)
+    pub fn set_chain_config(
+        ctx: Context<SetChainConfig>,
+        chain_id: u16,
+        eid: u32,
+        executor_options: ExecutorOptions,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.vault_store.supported_chains.contains(&chain_id),
+            OmniVaultError::UnsupportedChain
+        );
+        require!(eid > 0, OmniVaultError::InvalidChainConfiguration);
+        require!(executor_options.lz_receive_gas > 0, OmniVaultError::InvalidExecutorOptions);
+        require!(
+            executor_options.native_drop_amount == 0 || executor_options.native_drop_receiver != [0u8; 32],
+            OmniVaultError::InvalidExecutorOptions
+        );
+        require!(
+            executor_options.compose_gas > 0 || executor_options.compose_value == 0,
+            OmniVaultError::InvalidExecutorOptions
+        );
+        
+        let chain_config = &mut ctx.accounts.chain_config;
+        chain_config.chain_id = chain_id;
+        chain_config.eid = eid;
+        chain_config.executor_options = executor_options;
+        chain_config.bump = ctx.bumps.chain_config;
+        
+        emit!(ChainConfigUpdated {
+            chain_id,
+            eid,
+            lz_receive_gas: chain_config.executor_options.lz_receive_gas,
+            updated_by: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Chain {} set to endpoint id {} with updated executor options", chain_id, eid);
+        Ok(())
+    }
+
+    /// Choose ordered or windowed unordered delivery for a vault's messages from a chain (owner only)
+    ///
+    /// Channels start unordered. Ordered is only safe when the vault is the sole receiver on
+    /// that chain's path, since the endpoint nonce is shared by every vault.
+    pub fn set_inbound_nonce_ordering(
+        ctx: Context<SetInboundNonceOrdering>,
+        src_chain_id: u16,
+        ordering: NonceOrdering,
+    ) -> Result<()> {
+        let inbound_nonce = &mut ctx.accounts.inbound_nonce;
+        if inbound_nonce.vault == Pubkey::default() {
+            inbound_nonce.vault = ctx.accounts.vault.key();
+            inbound_nonce.src_chain_id = src_chain_id;
+            inbound_nonce.bump = ctx.bumps.inbound_nonce;
+        }
+        inbound_nonce.ordering = ordering;
+        
+        msg!(
+            "Vault {} accepts {:?} nonces from chain {}",
+            ctx.accounts.vault.id, inbound_nonce.ordering, src_chain_id
+        );
+        Ok(())
+    }
+
     /// Manual rebalance vault strategy (admin only)
     pub fn rebalance_vault(ctx: Context<RebalanceVault>, target_chain: u16) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
         let vault_store = &ctx.accounts.vault_store;
         
-        require!(!vault_store.emergency_pause, OmniVaultError::SystemPaused);
+        require_not_paused(vault_store, vault, pause_flags::REBALANCE)?;
         require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
+        require!(vault.target_chains.contains(&target_chain), OmniVaultError::InvalidTargetChain);
         
         let clock = Clock::get()?;
         require!(
@@ -547,55 +1515,518 @@ pub mod omnivault {
         Ok(())
     }
 
-    /// Update vault configuration (owner only)
-    pub fn update_vault_config(
-        ctx: Context<UpdateVaultConfig>,
-        new_min_deposit: Option<u64>,
-        new_active_status: Option<bool>,
-        new_rebalance_threshold: Option<u64>,
-        new_target_chains: Option<Vec<u16>>,
-    ) -> Result<()> {
+    /// Toggle whether a vault accepts deposits (owner only, applies immediately)
+    pub fn set_vault_active(ctx: Context<SetVaultActive>, is_active: bool) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
+        vault.is_active = is_active;
         
-        if let Some(min_deposit) = new_min_deposit {
-            require!(min_deposit > 0, OmniVaultError::InvalidAmount);
-            vault.min_deposit = min_deposit;
-        }
+        emit!(VaultConfigUpdated {
+            vault_id: vault.id,
+            updated_by: ctx.accounts.owner.key(),
+        });
         
-        if let Some(active_status) = new_active_status {
-            vault.is_active = active_status;
-        }
+        msg!("Vault {} active status set to {}", vault.id, is_active);
+        Ok(())
+    }
+
+    /// Close a user position that no longer holds shares, returning its rent to the user
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let user_position = &ctx.accounts.user_position;
+        
+        require!(ctx.accounts.user_share_account.amount == 0, OmniVaultError::PositionNotEmpty);
+        // A reopened position starts with no deposit time, so closing must not cut a lock short
+        let unlocks_at = user_position.last_deposit.saturating_add(vault.lock_period);
+        require!(Clock::get()?.unix_timestamp >= unlocks_at, OmniVaultError::PositionLocked);
+        
+        emit!(PositionClosed {
+            vault_id: vault.id,
+            user: ctx.accounts.user.key(),
+        });
+        
+        msg!("Position of {} in vault {} closed", ctx.accounts.user.key(), vault.id);
+        Ok(())
+    }
+
+    /// Close an additional-asset position with no shares left, returning its rent to the user
+    pub fn close_asset_position(ctx: Context<CloseAssetPosition>) -> Result<()> {
+        require!(ctx.accounts.asset_position.shares == 0, OmniVaultError::PositionNotEmpty);
+        
+        emit!(PositionClosed {
+            vault_id: ctx.accounts.vault.id,
+            user: ctx.accounts.user.key(),
+        });
+        
+        msg!("Asset position of {} in {} closed", ctx.accounts.user.key(), ctx.accounts.vault_asset.mint);
+        Ok(())
+    }
+
+    /// Remove a drained additional asset from a vault and reclaim its accounts (owner only)
+    pub fn remove_vault_asset<'info>(ctx: Context<'_, '_, 'info, 'info, RemoveVaultAsset<'info>>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let vault_asset = &ctx.accounts.vault_asset;
+        
+        require!(
+            vault_asset.total_shares == 0 && vault_asset.accrued_fees == 0,
+            OmniVaultError::VaultNotEmpty
+        );
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        sweep_and_close(
+            &ctx.accounts.token_program,
+            &ctx.accounts.asset_token_account,
+            &ctx.accounts.asset_mint,
+            ctx.accounts.owner_token_account.as_ref(),
+            ctx.accounts.owner.to_account_info(),
+            vault.to_account_info(),
+            ctx.remaining_accounts,
+            signer_seeds,
+        )?;
+        
+        let mint = vault_asset.mint;
+        vault.extra_assets.retain(|extra| *extra != mint);
+        
+        emit!(VaultAssetRemoved {
+            vault_id: vault.id,
+            mint,
+        });
+        
+        msg!("Asset {} removed from vault {}", mint, vault.id);
+        Ok(())
+    }
+
+    /// Close a deactivated vault with no shares, assets or pending withdrawals (owner only)
+    /// The vault's InboundNonce accounts are closed too when passed among the remaining accounts
+    pub fn close_vault<'info>(ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        
+        require!(!vault.is_active, OmniVaultError::VaultStillActive);
+        require!(vault.extra_assets.is_empty(), OmniVaultError::VaultNotEmpty);
+        require!(ctx.accounts.share_mint.supply == 0, OmniVaultError::VaultNotEmpty);
+        require!(
+            vault.total_assets() == 0 && vault.accrued_fees == 0
+                && vault.emergency_claims == 0
+                && vault.emergency_reserved == 0,
+            OmniVaultError::VaultNotEmpty
+        );
+        require!(
+            vault.withdrawal_queue_head == vault.withdrawal_queue_tail && vault.reserved_withdrawals == 0,
+            OmniVaultError::VaultNotEmpty
+        );
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        // A queued change must not outlive the vault, or it could be executed against the closed address
+        if ctx.accounts.pending_config.owner == &crate::ID {
+            close_program_account(
+                &ctx.accounts.pending_config.to_account_info(),
+                &ctx.accounts.owner.to_account_info(),
+            )?;
+        }
+        let transfer_accounts = close_inbound_nonces(
+            vault.key(),
+            ctx.remaining_accounts,
+            ctx.accounts.owner.to_account_info(),
+        )?;
+        sweep_and_close(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.asset_mint,
+            ctx.accounts.owner_token_account.as_ref(),
+            ctx.accounts.owner.to_account_info(),
+            vault.to_account_info(),
+            &transfer_accounts,
+            signer_seeds,
+        )?;
+        // The escrows only exist once a withdrawal has been queued or the vault migrated,
+        // and hold no shares once the share supply is zero
+        for escrow in [&ctx.accounts.share_escrow, &ctx.accounts.legacy_share_escrow].into_iter().flatten() {
+            token_interface::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: escrow.to_account_info(),
+                    destination: ctx.accounts.owner.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                signer_seeds,
+            ))?;
+        }
+        
+        emit!(VaultClosed {
+            vault_id: vault.id,
+            owner: vault.owner,
+        });
         
-        if let Some(threshold) = new_rebalance_threshold {
-            require!(threshold > 0 && threshold <= 1000, OmniVaultError::InvalidThreshold); // Max 10%
+        msg!("Vault {} closed", vault.id);
+        Ok(())
+    }
+
+    /// Queue a vault strategy change behind the store timelock (owner only)
+    #[allow(clippy::too_many_arguments)]
+    pub fn queue_vault_config(
+        ctx: Context<QueueVaultConfig>,
+        new_min_deposit: Option<u64>,
+        new_rebalance_threshold: Option<u64>,
+        new_target_chains: Option<Vec<u16>>,
+        new_management_fee_bps: Option<u16>,
+        new_performance_fee_bps: Option<u16>,
+        new_deposit_cap: Option<u64>,
+        new_lock_period: Option<i64>,
+        new_early_exit_penalty_bps: Option<u16>,
+    ) -> Result<()> {
+        let change = ConfigChange::Vault {
+            min_deposit: new_min_deposit,
+            rebalance_threshold: new_rebalance_threshold,
+            target_chains: new_target_chains,
+            management_fee_bps: new_management_fee_bps,
+            performance_fee_bps: new_performance_fee_bps,
+            deposit_cap: new_deposit_cap,
+            lock_period: new_lock_period,
+            early_exit_penalty_bps: new_early_exit_penalty_bps,
+        };
+        validate_config_change(&change, &ctx.accounts.vault_store)?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        let pending_config = &mut ctx.accounts.pending_config;
+        pending_config.target = ctx.accounts.vault.key();
+        pending_config.change = change;
+        pending_config.proposer = ctx.accounts.owner.key();
+        pending_config.queued_at = now;
+        pending_config.eta = now + ctx.accounts.vault_store.config_delay;
+        pending_config.bump = ctx.bumps.pending_config;
+        
+        emit!(ConfigChangeQueued {
+            target: pending_config.target,
+            proposer: pending_config.proposer,
+            eta: pending_config.eta,
+        });
+        
+        msg!("Vault {} configuration change queued, executable at {}", ctx.accounts.vault.id, pending_config.eta);
+        Ok(())
+    }
+
+    /// Apply a queued vault change once its timelock has expired
+    pub fn execute_vault_config(ctx: Context<ExecuteVaultConfig>) -> Result<()> {
+        let pending_config = &ctx.accounts.pending_config;
+        require!(
+            Clock::get()?.unix_timestamp >= pending_config.eta,
+            OmniVaultError::TimelockNotExpired
+        );
+        // Store parameters may have changed while the change was queued
+        validate_config_change(&pending_config.change, &ctx.accounts.vault_store)?;
+        
+        let vault = &mut ctx.accounts.vault;
+        let ConfigChange::Vault {
+            min_deposit,
+            rebalance_threshold,
+            target_chains,
+            management_fee_bps,
+            performance_fee_bps,
+            deposit_cap,
+            lock_period,
+            early_exit_penalty_bps,
+        } = pending_config.change.clone() else {
+            return Err(OmniVaultError::InvalidConfigChange.into());
+        };
+        
+        if let Some(min_deposit) = min_deposit {
+            vault.min_deposit = min_deposit;
+        }
+        
+        if let Some(threshold) = rebalance_threshold {
             vault.rebalance_threshold = threshold;
         }
         
-        if let Some(chains) = new_target_chains {
-            require!(!chains.is_empty() && chains.len() <= 10, OmniVaultError::InvalidChainConfiguration);
+        if let Some(chains) = target_chains {
             vault.target_chains = chains;
         }
         
+        if let Some(deposit_cap) = deposit_cap {
+            vault.deposit_cap = deposit_cap;
+        }
+        
+        if let Some(lock_period) = lock_period {
+            vault.lock_period = lock_period;
+        }
+        
+        if let Some(penalty_bps) = early_exit_penalty_bps {
+            vault.early_exit_penalty_bps = penalty_bps;
+        }
+        
+        if management_fee_bps.is_some() || performance_fee_bps.is_some() {
+            // Settle fees owed under the old rates before switching
+            let nav_before = vault.total_assets();
+            accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+            sync_tvl(&mut ctx.accounts.vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+            vault.management_fee_bps = management_fee_bps.unwrap_or(vault.management_fee_bps);
+            vault.performance_fee_bps = performance_fee_bps.unwrap_or(vault.performance_fee_bps);
+            
+            emit!(VaultFeesUpdated {
+                vault_id: vault.id,
+                management_fee_bps: vault.management_fee_bps,
+                performance_fee_bps: vault.performance_fee_bps,
+                updated_by: ctx.accounts.executor.key(),
+            });
+        }
+        
         emit!(VaultConfigUpdated {
             vault_id: vault.id,
-            updated_by: ctx.accounts.owner.key(),
+            updated_by: ctx.accounts.executor.key(),
         });
         
         msg!("Vault {} configuration updated", vault.id);
         Ok(())
     }
 
-    /// Emergency pause system (admin only)
+    /// Queue a protocol-wide store change behind the store timelock (admin only)
+    pub fn queue_store_config(
+        ctx: Context<QueueStoreConfig>,
+        new_fee_rate: Option<u16>,
+        add_chains: Vec<u16>,
+        remove_chains: Vec<u16>,
+        new_config_delay: Option<i64>,
+        new_global_deposit_cap: Option<MintDepositCap>,
+    ) -> Result<()> {
+        let change = ConfigChange::Store {
+            fee_rate: new_fee_rate,
+            add_chains,
+            remove_chains,
+            config_delay: new_config_delay,
+            global_deposit_cap: new_global_deposit_cap,
+        };
+        validate_config_change(&change, &ctx.accounts.vault_store)?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        let pending_config = &mut ctx.accounts.pending_config;
+        pending_config.target = ctx.accounts.vault_store.key();
+        pending_config.change = change;
+        pending_config.proposer = ctx.accounts.authority.key();
+        pending_config.queued_at = now;
+        pending_config.eta = now + ctx.accounts.vault_store.config_delay;
+        pending_config.bump = ctx.bumps.pending_config;
+        
+        emit!(ConfigChangeQueued {
+            target: pending_config.target,
+            proposer: pending_config.proposer,
+            eta: pending_config.eta,
+        });
+        
+        msg!("Store configuration change queued, executable at {}", pending_config.eta);
+        Ok(())
+    }
+
+    /// Apply a queued store change once its timelock has expired
+    pub fn execute_store_config(ctx: Context<ExecuteStoreConfig>) -> Result<()> {
+        let pending_config = &ctx.accounts.pending_config;
+        require!(
+            Clock::get()?.unix_timestamp >= pending_config.eta,
+            OmniVaultError::TimelockNotExpired
+        );
+        validate_config_change(&pending_config.change, &ctx.accounts.vault_store)?;
+        
+        let vault_store = &mut ctx.accounts.vault_store;
+        let ConfigChange::Store {
+            fee_rate,
+            add_chains,
+            remove_chains,
+            config_delay,
+            global_deposit_cap,
+        } = pending_config.change.clone() else {
+            return Err(OmniVaultError::InvalidConfigChange.into());
+        };
+        
+        if let Some(fee_rate) = fee_rate {
+            vault_store.fee_rate = fee_rate;
+        }
+        
+        if let Some(config_delay) = config_delay {
+            vault_store.config_delay = config_delay;
+        }
+        
+        if let Some(global_deposit_cap) = &global_deposit_cap {
+            vault_store.mint_tvl_mut(global_deposit_cap.mint)?.deposit_cap = global_deposit_cap.cap;
+        }
+        
+        vault_store.supported_chains.retain(|chain| !remove_chains.contains(chain));
+        for chain in add_chains {
+            if !vault_store.supported_chains.contains(&chain) {
+                vault_store.supported_chains.push(chain);
+            }
+        }
+        
+        emit!(StoreConfigUpdated {
+            fee_rate: vault_store.fee_rate,
+            supported_chains: vault_store.supported_chains.clone(),
+            config_delay: vault_store.config_delay,
+            global_deposit_cap,
+            updated_by: ctx.accounts.executor.key(),
+        });
+        
+        msg!("Store configuration updated by {}", ctx.accounts.executor.key());
+        Ok(())
+    }
+
+    /// Cancel a queued configuration change (guardian, admin or proposer)
+    pub fn cancel_config(ctx: Context<CancelConfig>) -> Result<()> {
+        let pending_config = &ctx.accounts.pending_config;
+        
+        emit!(ConfigChangeCancelled {
+            target: pending_config.target,
+            cancelled_by: ctx.accounts.canceller.key(),
+        });
+        
+        msg!("Configuration change for {} cancelled by {}", pending_config.target, ctx.accounts.canceller.key());
+        Ok(())
+    }
+
+    /// Propose a new store authority; takes effect once accepted (admin only)
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let vault_store = &mut ctx.accounts.vault_store;
+        // Proposing the default key cancels a pending transfer
+        vault_store.pending_authority = new_authority;
+        
+        emit!(AuthorityTransferProposed {
+            current_authority: vault_store.authority,
+            pending_authority: new_authority,
+        });
+        
+        msg!("Store authority transfer proposed to {}", new_authority);
+        Ok(())
+    }
+
+    /// Accept a pending store authority transfer (pending authority only)
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let vault_store = &mut ctx.accounts.vault_store;
+        let previous_authority = vault_store.authority;
+        vault_store.authority = vault_store.pending_authority;
+        vault_store.pending_authority = Pubkey::default();
+        
+        emit!(AuthorityTransferred {
+            previous_authority,
+            new_authority: vault_store.authority,
+        });
+        
+        msg!("Store authority transferred from {} to {}", previous_authority, vault_store.authority);
+        Ok(())
+    }
+
+    /// Propose a new vault owner; takes effect once accepted (owner only)
+    pub fn propose_vault_owner(ctx: Context<ProposeVaultOwner>, new_owner: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        // Proposing the default key cancels a pending transfer
+        vault.pending_owner = new_owner;
+        
+        emit!(VaultOwnershipProposed {
+            vault_id: vault.id,
+            current_owner: vault.owner,
+            pending_owner: new_owner,
+        });
+        
+        msg!("Vault {} ownership transfer proposed to {}", vault.id, new_owner);
+        Ok(())
+    }
+
+    /// Accept a pending vault ownership transfer (pending owner only).
+    /// The vault PDA is derived from `creator`, so its address does not change.
+    pub fn accept_vault_owner(ctx: Context<AcceptVaultOwner>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let previous_owner = vault.owner;
+        vault.owner = vault.pending_owner;
+        vault.pending_owner = Pubkey::default();
+        
+        emit!(VaultOwnershipTransferred {
+            vault_id: vault.id,
+            previous_owner,
+            new_owner: vault.owner,
+        });
+        
+        msg!("Vault {} ownership transferred from {} to {}", vault.id, previous_owner, vault.owner);
+        Ok(())
+    }
+
+    /// Create the keeper, guardian and fee-manager registry (admin only)
+    pub fn initialize_roles(ctx: Context<InitializeRoles>) -> Result<()> {
+        let roles = &mut ctx.accounts.roles;
+        roles.vault_store = ctx.accounts.vault_store.key();
+        roles.keepers = vec![];
+        roles.guardians = vec![];
+        roles.fee_managers = vec![];
+        roles.bump = ctx.bumps.roles;
+        
+        msg!("Role registry initialized for store {}", roles.vault_store);
+        Ok(())
+    }
+
+    /// Grant a role to a key (admin only)
+    pub fn grant_role(ctx: Context<UpdateRoles>, role: Role, member: Pubkey) -> Result<()> {
+        let roles = &mut ctx.accounts.roles;
+        let (members, max_members) = match role {
+            Role::Keeper => (&mut roles.keepers, MAX_KEEPERS),
+            Role::Guardian => (&mut roles.guardians, MAX_GUARDIANS),
+            Role::FeeManager => (&mut roles.fee_managers, MAX_FEE_MANAGERS),
+        };
+        require!(!members.contains(&member), OmniVaultError::RoleAlreadyGranted);
+        require!(members.len() < max_members, OmniVaultError::TooManyRoleMembers);
+        members.push(member);
+        
+        emit!(RoleGranted {
+            role: role.clone(),
+            member,
+            granted_by: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Granted {:?} role to {}", role, member);
+        Ok(())
+    }
+
+    /// Revoke a role from a key (admin only)
+    pub fn revoke_role(ctx: Context<UpdateRoles>, role: Role, member: Pubkey) -> Result<()> {
+        let roles = &mut ctx.accounts.roles;
+        let members = match role {
+            Role::Keeper => &mut roles.keepers,
+            Role::Guardian => &mut roles.guardians,
+            Role::FeeManager => &mut roles.fee_managers,
+        };
+        let index = members
+            .iter()
+            .position(|key| *key == member)
+            .ok_or(OmniVaultError::RoleNotGranted)?;
+        members.remove(index);
+        
+        emit!(RoleRevoked {
+            role: role.clone(),
+            member,
+            revoked_by: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Revoked {:?} role from {}", role, member);
+        Ok(())
+    }
+
+    /// Emergency pause system (guardian or admin)
     pub fn emergency_pause(ctx: Context<EmergencyPause>) -> Result<()> {
         let vault_store = &mut ctx.accounts.vault_store;
         vault_store.emergency_pause = true;
         
         emit!(SystemEmergencyPause {
-            triggered_by: ctx.accounts.authority.key(),
+            triggered_by: ctx.accounts.guardian.key(),
             timestamp: Clock::get()?.unix_timestamp,
         });
         
-        msg!("System emergency pause activated by {}", ctx.accounts.authority.key());
+        msg!("System emergency pause activated by {}", ctx.accounts.guardian.key());
         Ok(())
     }
 
@@ -613,533 +2044,3979 @@ pub mod omnivault {
         Ok(())
     }
 
-    /// Deposit USDC via CCTP from another chain
-    pub fn deposit_usdc_via_cctp(
-        ctx: Context<DepositUSDCViaCCTP>,
-        amount: u64,
-        source_domain: u32,
-        attestation: Vec<u8>,
-    ) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        let vault_store = &ctx.accounts.vault_store;
-        let cctp_config = &ctx.accounts.cctp_config;
-
-        // Verify system is not paused
-        require!(!vault_store.emergency_pause, OmniVaultError::SystemPaused);
-        require!(vault.is_active, OmniVaultError::VaultInactive);
-
-        // Verify CCTP attestation
-        require!(!attestation.is_empty(), OmniVaultError::InvalidAttestation);
-
-        // Verify amount meets minimum deposit
-        require!(amount >= vault.min_deposit, OmniVaultError::DepositTooSmall);
+    /// Pause operations protocol-wide by pause_flags bit (guardian or admin)
+    pub fn set_store_pause_flags(ctx: Context<SetStorePauseFlags>, flags: u8) -> Result<()> {
+        require!(flags != 0 && flags & !pause_flags::ALL == 0, OmniVaultError::InvalidPauseFlags);
+        
+        let vault_store = &mut ctx.accounts.vault_store;
+        let previous_flags = vault_store.pause_flags;
+        vault_store.pause_flags |= flags;
+        
+        emit!(PauseFlagsChanged {
+            vault: None,
+            previous_flags,
+            pause_flags: vault_store.pause_flags,
+            changed_by: ctx.accounts.guardian.key(),
+        });
+        
+        msg!("Store pause flags set to {:#07b}", vault_store.pause_flags);
+        Ok(())
+    }
 
-        // Update user position
-        let user_position = &mut ctx.accounts.user_position;
-        user_position.amount += amount;
-        user_position.last_deposit = Clock::get()?.unix_timestamp;
+    /// Lift protocol-wide pause flags (admin only)
+    pub fn clear_store_pause_flags(ctx: Context<ClearStorePauseFlags>, flags: u8) -> Result<()> {
+        require!(flags != 0 && flags & !pause_flags::ALL == 0, OmniVaultError::InvalidPauseFlags);
+        
+        let vault_store = &mut ctx.accounts.vault_store;
+        let previous_flags = vault_store.pause_flags;
+        vault_store.pause_flags &= !flags;
+        
+        emit!(PauseFlagsChanged {
+            vault: None,
+            previous_flags,
+            pause_flags: vault_store.pause_flags,
+            changed_by: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Store pause flags set to {:#07b}", vault_store.pause_flags);
+        Ok(())
+    }
 
-        // Update vault totals
-        vault.total_deposits += amount;
+    /// Pause operations on a single vault by pause_flags bit (guardian or admin)
+    pub fn set_vault_pause_flags(ctx: Context<SetVaultPauseFlags>, flags: u8) -> Result<()> {
+        require!(flags != 0 && flags & !pause_flags::ALL == 0, OmniVaultError::InvalidPauseFlags);
+        
+        let vault = &mut ctx.accounts.vault;
+        let previous_flags = vault.pause_flags;
+        vault.pause_flags |= flags;
+        
+        emit!(PauseFlagsChanged {
+            vault: Some(vault.key()),
+            previous_flags,
+            pause_flags: vault.pause_flags,
+            changed_by: ctx.accounts.guardian.key(),
+        });
+        
+        msg!("Vault {} pause flags set to {:#07b}", vault.id, vault.pause_flags);
+        Ok(())
+    }
 
-        // Check if automatic yield optimization should trigger
-        if vault.total_deposits > vault.rebalance_threshold {
-            msg!("Deposit threshold reached, consider rebalancing");
-        }
+    /// Lift pause flags on a single vault (admin only)
+    pub fn clear_vault_pause_flags(ctx: Context<ClearVaultPauseFlags>, flags: u8) -> Result<()> {
+        require!(flags != 0 && flags & !pause_flags::ALL == 0, OmniVaultError::InvalidPauseFlags);
+        
+        let vault = &mut ctx.accounts.vault;
+        let previous_flags = vault.pause_flags;
+        vault.pause_flags &= !flags;
+        
+        emit!(PauseFlagsChanged {
+            vault: Some(vault.key()),
+            previous_flags,
+            pause_flags: vault.pause_flags,
+            changed_by: ctx.accounts.authority.key(),
+        });
+        
+        msg!("Vault {} pause flags set to {:#07b}", vault.id, vault.pause_flags);
+        Ok(())
+    }
 
-        emit!(CCTPDepositMade {
+    /// Put a vault into emergency exit so holders redeem pro rata (guardian or admin)
+    pub fn trigger_emergency_exit(ctx: Context<SetVaultPauseFlags>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.enter_emergency();
+        
+        emit!(EmergencyExitChanged {
             vault_id: vault.id,
-            user: ctx.accounts.user.key(),
-            amount,
-            source_domain,
-            new_total: vault.total_deposits,
+            emergency_exit: true,
+            changed_by: ctx.accounts.guardian.key(),
         });
-
+        
+        msg!("Vault {} emergency exit triggered by {}", vault.id, ctx.accounts.guardian.key());
         Ok(())
     }
 
-    /// Withdraw USDC via CCTP to another chain
-    pub fn withdraw_usdc_via_cctp(
-        ctx: Context<WithdrawUSDCViaCCTP>,
-        amount: u64,
-        destination_domain: u32,
-        destination_address: Vec<u8>,
-    ) -> Result<()> {
+    /// Take a vault out of emergency exit (admin only)
+    pub fn clear_emergency_exit(ctx: Context<ClearVaultPauseFlags>) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
-        let vault_store = &ctx.accounts.vault_store;
-        let user_position = &mut ctx.accounts.user_position;
-        let cctp_config = &ctx.accounts.cctp_config;
-
-        // Verify system is not paused
-        require!(!vault_store.emergency_pause, OmniVaultError::SystemPaused);
-        require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
-
-        // Check sufficient balance
-        require!(user_position.amount >= amount, OmniVaultError::InsufficientBalance);
-
-        // Calculate withdrawal fee (1% for CCTP Fast Transfer)
-        let fee = amount.checked_mul(cctp_config.fee_rate as u64)
-            .unwrap()
-            .checked_div(10000).unwrap();
-        let net_amount = amount.checked_sub(fee).unwrap();
-
-        // Update user position
-        user_position.amount = user_position.amount.checked_sub(amount).unwrap();
-        user_position.last_withdrawal = Clock::get()?.unix_timestamp;
-
-        // Update vault totals
-        vault.total_deposits = vault.total_deposits.checked_sub(amount).unwrap();
-        vault.total_yield += fee;
-
-        // Initiate CCTP burn for cross-chain transfer
-        msg!("Initiating CCTP burn for {} USDC to domain {}", net_amount, destination_domain);
-
-        emit!(CCTPWithdrawalMade {
+        vault.emergency_exit = false;
+        
+        emit!(EmergencyExitChanged {
             vault_id: vault.id,
-            user: ctx.accounts.user.key(),
-            amount: net_amount,
-            fee,
-            destination_domain,
+            emergency_exit: false,
+            changed_by: ctx.accounts.authority.key(),
         });
+        
+        msg!("Vault {} emergency exit cleared by {}", vault.id, ctx.accounts.authority.key());
+        Ok(())
+    }
 
+    /// Upgrade the vault store to the current schema version (admin only)
+    pub fn migrate_vault_store(ctx: Context<MigrateVaultStore>) -> Result<()> {
+        let account = ctx.accounts.vault_store.to_account_info();
+        resize_for_migration(&account, &ctx.accounts.authority, &ctx.accounts.system_program, 8 + VaultStore::INIT_SPACE)?;
+        
+        let mut vault_store = VaultStore::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        require!(vault_store.version < VAULT_STORE_VERSION, OmniVaultError::AccountVersionMismatch);
+        require!(vault_store.authority == ctx.accounts.authority.key(), OmniVaultError::UnauthorizedCaller);
+        let from_version = vault_store.version;
+        
+        // Fields appended before versioning read back as zero on legacy accounts
+        if vault_store.config_delay == 0 {
+            vault_store.config_delay = DEFAULT_CONFIG_DELAY;
+        }
+        vault_store.version = VAULT_STORE_VERSION;
+        vault_store.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+        
+        emit!(AccountMigrated {
+            account: account.key(),
+            from_version,
+            to_version: VAULT_STORE_VERSION,
+        });
+        
+        msg!("Vault store migrated from version {} to {}", from_version, VAULT_STORE_VERSION);
         Ok(())
     }
 
-    /// Rebalance vault using CCTP Fast Transfer
-    pub fn rebalance_with_cctp(
-        ctx: Context<RebalanceWithCCTP>,
-        target_domain: u32,
-        amount: u64,
+    /// Upgrade a vault created before versioning (owner only). Creates the share mint and
+    /// token account, sweeps the legacy token account into it and mints shares for legacy
+    /// deposits into an escrow that migrate_user_position pays out from.
+    pub fn migrate_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateVault<'info>>,
+        asset_kind: AssetKind,
     ) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        let yield_tracker = &mut ctx.accounts.yield_tracker;
-        let cctp_config = &ctx.accounts.cctp_config;
-
-        // Verify rebalancing is not too frequent
-        let current_time = Clock::get()?.unix_timestamp;
         require!(
-            current_time - vault.last_rebalance >= MIN_REBALANCE_INTERVAL,
-            OmniVaultError::RebalanceTooFrequent
+            asset_kind == AssetKind::Spl || ctx.accounts.asset_mint.key() == native_mint::ID,
+            OmniVaultError::AssetKindMismatch
+        );
+        let account = ctx.accounts.vault.to_account_info();
+        // Only vaults created before versioning still have the original layout
+        require!(account.data_len() == 8 + VaultV0::INIT_SPACE, OmniVaultError::AccountVersionMismatch);
+        let legacy = VaultV0::try_from_account_data(&account.try_borrow_data()?)?;
+        require!(legacy.owner == ctx.accounts.owner.key(), OmniVaultError::UnauthorizedCaller);
+        let vault_address = Pubkey::create_program_address(
+            &[b"vault", legacy.owner.as_ref(), &legacy.id.to_le_bytes(), &[legacy.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| OmniVaultError::InvalidVaultId)?;
+        require!(vault_address == account.key(), OmniVaultError::InvalidVaultId);
+        
+        let legacy_shares = legacy.total_deposits;
+        ctx.accounts.vault_store.mint_tvl_mut(ctx.accounts.asset_mint.key())?;
+        let vault = legacy.into_vault(
+            ctx.accounts.share_mint.key(),
+            ctx.accounts.asset_mint.key(),
+            ctx.accounts.vault_token_account.key(),
+            asset_kind,
+            Clock::get()?.unix_timestamp,
         );
+        resize_for_migration(&account, &ctx.accounts.owner, &ctx.accounts.system_program, 8 + Vault::INIT_SPACE)?;
+        vault.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        if let Some(legacy_token_account) = &ctx.accounts.legacy_token_account {
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                legacy_token_account.to_account_info(),
+                &ctx.accounts.asset_mint,
+                ctx.accounts.vault_token_account.to_account_info(),
+                account.clone(),
+                ctx.remaining_accounts,
+                legacy_token_account.amount,
+                signer_seeds,
+            )?;
+        }
+        
+        // Legacy positions were credited 1:1 in base units, so their principal mints 1:1 in shares
+        if legacy_shares > 0 {
+            let cpi_accounts = MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.legacy_share_escrow.to_account_info(),
+                authority: account.clone(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer_seeds);
+            token_interface::mint_to(cpi_ctx, legacy_shares)?;
+        }
+        
+        let tracker_account = ctx.accounts.yield_tracker.to_account_info();
+        resize_for_migration(&tracker_account, &ctx.accounts.owner, &ctx.accounts.system_program, 8 + YieldTracker::INIT_SPACE)?;
+        let mut yield_tracker = YieldTracker::try_deserialize(&mut &tracker_account.try_borrow_data()?[..])?;
+        if yield_tracker.version < YIELD_TRACKER_VERSION {
+            yield_tracker.version = YIELD_TRACKER_VERSION;
+            yield_tracker.try_serialize(&mut &mut tracker_account.try_borrow_mut_data()?[..])?;
+        }
+        
+        emit!(AccountMigrated {
+            account: account.key(),
+            from_version: 0,
+            to_version: VAULT_VERSION,
+        });
+        
+        msg!("Vault {} migrated to version {} with {} legacy shares", vault.id, VAULT_VERSION, legacy_shares);
+        Ok(())
+    }
 
-        // Find the chain yield data for target domain
-        let chain_id = cctp_domain_to_chain_id(target_domain);
-        let target_yield = yield_tracker.chain_yields
+    /// Upgrade a user position to the current schema version, paying out shares for its legacy
+    /// principal (anyone, payer funds rent)
+    pub fn migrate_user_position(ctx: Context<MigrateUserPosition>) -> Result<()> {
+        let account = ctx.accounts.user_position.to_account_info();
+        resize_for_migration(&account, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + UserPosition::INIT_SPACE)?;
+        
+        // UserPosition only ever had fixed-size fields, so the ones appended since read back as zero
+        let mut user_position = UserPosition::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        require!(user_position.version < USER_POSITION_VERSION, OmniVaultError::AccountVersionMismatch);
+        let vault = &ctx.accounts.vault;
+        require!(user_position.vault == vault.key(), OmniVaultError::InvalidVaultId);
+        require!(user_position.user == ctx.accounts.user.key(), OmniVaultError::UnauthorizedCaller);
+        let from_version = user_position.version;
+        
+        let shares = user_position.amount.min(ctx.accounts.legacy_share_escrow.amount);
+        if shares > 0 {
+            let bump = &[vault.bump];
+            let vault_seeds = &[
+                b"vault".as_ref(),
+                vault.creator.as_ref(),
+                &vault.id.to_le_bytes(),
+                bump,
+            ];
+            let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                ctx.accounts.legacy_share_escrow.to_account_info(),
+                &ctx.accounts.share_mint,
+                ctx.accounts.user_share_account.to_account_info(),
+                vault.to_account_info(),
+                &[],
+                shares,
+                signer_seeds,
+            )?;
+        }
+        
+        user_position.shares = shares;
+        user_position.locked_shares = 0;
+        user_position.version = USER_POSITION_VERSION;
+        user_position.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+        
+        emit!(AccountMigrated {
+            account: account.key(),
+            from_version,
+            to_version: USER_POSITION_VERSION,
+        });
+        
+        msg!("Position {} migrated from version {} to {} with {} shares", account.key(), from_version, USER_POSITION_VERSION, shares);
+        Ok(())
+    }
+
+    /// Create an M-of-N multisig whose signer PDA can act as VaultStore.authority
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        create_key: Pubkey,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        validate_multisig_signers(&signers, threshold)?;
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.create_key = create_key;
+        multisig.signers = signers;
+        multisig.threshold = threshold;
+        multisig.signer_set_seqno = 0;
+        multisig.proposal_count = 0;
+        multisig.signer_bump = ctx.bumps.multisig_signer;
+        multisig.bump = ctx.bumps.multisig;
+        
+        emit!(MultisigCreated {
+            multisig: multisig.key(),
+            multisig_signer: ctx.accounts.multisig_signer.key(),
+            signers: multisig.signers.clone(),
+            threshold,
+        });
+        
+        msg!("Multisig {} created with threshold {}/{}", multisig.key(), threshold, multisig.signers.len());
+        Ok(())
+    }
+
+    /// Propose an instruction for the multisig signer to execute (multisig signer set only).
+    /// The proposer's approval is recorded automatically.
+    pub fn create_multisig_proposal(
+        ctx: Context<CreateMultisigProposal>,
+        accounts: Vec<ProposalAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let signer_index = multisig
+            .signers
             .iter()
-            .find(|cy| cy.chain_id == chain_id)
-            .ok_or(OmniVaultError::InvalidTargetChain)?;
+            .position(|key| *key == ctx.accounts.proposer.key())
+            .ok_or(OmniVaultError::UnauthorizedCaller)?;
+        
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.multisig = multisig.key();
+        proposal.index = multisig.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.accounts = accounts;
+        proposal.data = data;
+        proposal.approvals = 1 << signer_index;
+        proposal.signer_set_seqno = multisig.signer_set_seqno;
+        proposal.executed = false;
+        proposal.bump = ctx.bumps.proposal;
+        
+        multisig.proposal_count += 1;
+        
+        emit!(MultisigProposalCreated {
+            multisig: multisig.key(),
+            index: proposal.index,
+            proposer: proposal.proposer,
+        });
+        
+        msg!("Multisig proposal {} created by {}", proposal.index, proposal.proposer);
+        Ok(())
+    }
 
-        // Verify rebalancing improves yield
+    /// Approve a pending multisig proposal (multisig signer set only)
+    pub fn approve_multisig_proposal(ctx: Context<ApproveMultisigProposal>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        
+        require!(!proposal.executed, OmniVaultError::ProposalAlreadyExecuted);
+        require!(proposal.signer_set_seqno == multisig.signer_set_seqno, OmniVaultError::StaleProposal);
+        
+        let signer_index = multisig
+            .signers
+            .iter()
+            .position(|key| *key == ctx.accounts.signer.key())
+            .ok_or(OmniVaultError::UnauthorizedCaller)?;
+        proposal.approvals |= 1 << signer_index;
+        
+        emit!(MultisigProposalApproved {
+            multisig: multisig.key(),
+            index: proposal.index,
+            signer: ctx.accounts.signer.key(),
+            approvals: proposal.approvals.count_ones() as u8,
+        });
+        
+        msg!("Multisig proposal {} approved by {}", proposal.index, ctx.accounts.signer.key());
+        Ok(())
+    }
+
+    /// Execute an approved proposal by invoking this program with the multisig signer PDA.
+    /// Accounts referenced by the proposal (and this program) are passed as remaining accounts.
+    pub fn execute_multisig_proposal(ctx: Context<ExecuteMultisigProposal>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        
+        require!(!proposal.executed, OmniVaultError::ProposalAlreadyExecuted);
+        require!(proposal.signer_set_seqno == multisig.signer_set_seqno, OmniVaultError::StaleProposal);
         require!(
-            target_yield.apy > vault.current_apy,
-            OmniVaultError::NoYieldImprovement
+            proposal.approvals.count_ones() >= multisig.threshold as u32,
+            OmniVaultError::NotEnoughApprovals
         );
+        
+        // Persist the executed flag before the CPI so the proposal cannot be replayed from within it
+        proposal.executed = true;
+        proposal.exit(&crate::ID)?;
+        
+        let multisig_key = multisig.key();
+        let multisig_signer = ctx.accounts.multisig_signer.key();
+        let instruction = Instruction {
+            program_id: crate::ID,
+            accounts: proposal
+                .accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer || account.pubkey == multisig_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: proposal.data.clone(),
+        };
+        
+        let bump = &[multisig.signer_bump];
+        let signer_seeds = &[
+            b"multisig_signer".as_ref(),
+            multisig_key.as_ref(),
+            bump,
+        ];
+        invoke_signed(&instruction, ctx.remaining_accounts, &[signer_seeds])?;
+        
+        emit!(MultisigProposalExecuted {
+            multisig: multisig_key,
+            index: proposal.index,
+            executed_by: ctx.accounts.executor.key(),
+        });
+        
+        msg!("Multisig proposal {} executed", proposal.index);
+        Ok(())
+    }
 
-        let yield_improvement = target_yield.apy - vault.current_apy;
-
-        // Update vault state
-        vault.last_rebalance = current_time;
-        vault.current_best_chain = chain_id;
-        vault.current_apy = target_yield.apy;
+    /// Replace the multisig signer set and threshold (multisig signer PDA only, via a proposal).
+    /// Pending proposals created under the old signer set become stale.
+    pub fn change_multisig(
+        ctx: Context<ChangeMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        validate_multisig_signers(&signers, threshold)?;
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.signers = signers;
+        multisig.threshold = threshold;
+        multisig.signer_set_seqno += 1;
+        
+        emit!(MultisigChanged {
+            multisig: multisig.key(),
+            signers: multisig.signers.clone(),
+            threshold,
+        });
+        
+        msg!("Multisig {} signer set changed, threshold {}/{}", multisig.key(), threshold, multisig.signers.len());
+        Ok(())
+    }
 
-        msg!("Rebalancing {} USDC to domain {} via CCTP Fast Transfer", amount, target_domain);
+    /// Create the protocol fee treasury for a mint (admin only)
+    pub fn initialize_fee_treasury(ctx: Context<InitializeFeeTreasury>) -> Result<()> {
+        let fee_treasury = &mut ctx.accounts.fee_treasury;
+        fee_treasury.mint = ctx.accounts.mint.key();
+        fee_treasury.token_account = ctx.accounts.treasury_token_account.key();
+        fee_treasury.total_collected = 0;
+        fee_treasury.bump = ctx.bumps.fee_treasury;
+        
+        msg!("Fee treasury initialized for mint {}", fee_treasury.mint);
+        Ok(())
+    }
 
-        emit!(CCTPRebalanceExecuted {
+    /// Move a vault's accrued withdrawal fees to the protocol treasury (fee manager or admin)
+    pub fn collect_fees<'info>(ctx: Context<'_, '_, 'info, 'info, CollectFees<'info>>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let fee_treasury = &mut ctx.accounts.fee_treasury;
+        
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        sync_tvl(&mut ctx.accounts.vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        let amount = vault.accrued_fees;
+        require!(amount > 0, OmniVaultError::NoFeesToCollect);
+        // Fees never come out of funds set aside for queued withdrawals or emergency claims
+        require!(
+            fee_liquidity(vault, ctx.accounts.vault_token_account.amount) >= amount,
+            OmniVaultError::InsufficientLiquidity
+        );
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.vault_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.treasury_token_account.to_account_info(),
+            vault.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
+        
+        vault.accrued_fees = 0;
+        fee_treasury.total_collected += amount;
+        
+        emit!(FeesCollected {
             vault_id: vault.id,
+            mint: fee_treasury.mint,
             amount,
-            from_chain: vault.current_best_chain,
-            to_domain: target_domain,
-            yield_improvement,
-            is_fast_transfer: true,
+            total_collected: fee_treasury.total_collected,
+            collected_by: ctx.accounts.fee_manager.key(),
         });
-
+        
+        msg!("Collected {} in fees from vault {}", amount, vault.id);
         Ok(())
     }
 
-    /// Process incoming CCTP hook for automated actions
-    pub fn handle_cctp_hook(
-        ctx: Context<HandleCCTPHook>,
-        hook_data: Vec<u8>,
-    ) -> Result<()> {
+    /// Move a vault's accrued native SOL fees to the protocol treasury (fee manager or admin)
+    pub fn collect_sol_fees(ctx: Context<CollectSolFees>) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
-        let hook_registry = &ctx.accounts.hook_registry;
-
-        // Parse hook data to determine action
-        require!(!hook_data.is_empty(), OmniVaultError::InvalidHookData);
-
-        let action_type = hook_data[0];
-        let action_data = &hook_data[1..];
-
-        match action_type {
-            1 => { // Deposit to vault
-                msg!("Processing automated vault deposit via CCTP hook");
-                // Process deposit logic
-            },
-            2 => { // Rebalance
-                msg!("Processing automated rebalance via CCTP hook");
-                // Process rebalance logic
-            },
-            3 => { // Compound yield
-                msg!("Processing yield compounding via CCTP hook");
-                // Process compound logic
+        let fee_treasury = &mut ctx.accounts.fee_treasury;
+        
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        sync_tvl(&mut ctx.accounts.vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+        let amount = vault.accrued_fees;
+        require!(amount > 0, OmniVaultError::NoFeesToCollect);
+        
+        // Fees only come out of lamports above rent that are not set aside for claimants
+        let vault_info = vault.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(vault_info.data_len());
+        require!(
+            fee_liquidity(vault, vault_info.lamports().saturating_sub(rent_floor)) >= amount,
+            OmniVaultError::InsufficientLiquidity
+        );
+        
+        // Wrap the fee lamports into the wSOL treasury account
+        **vault.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.treasury_token_account.to_account_info().try_borrow_mut_lamports()? += amount;
+        token_interface::sync_native(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::SyncNative {
+                account: ctx.accounts.treasury_token_account.to_account_info(),
             },
-            _ => {
-                return Err(OmniVaultError::UnsupportedHookAction.into());
-            }
-        }
-
-        emit!(CCTPHookExecuted {
+        ))?;
+        
+        vault.accrued_fees = 0;
+        fee_treasury.total_collected += amount;
+        
+        emit!(FeesCollected {
             vault_id: vault.id,
-            action_type,
-            timestamp: Clock::get()?.unix_timestamp,
+            mint: fee_treasury.mint,
+            amount,
+            total_collected: fee_treasury.total_collected,
+            collected_by: ctx.accounts.fee_manager.key(),
         });
-
+        
+        msg!("Collected {} lamports in fees from vault {}", amount, vault.id);
         Ok(())
     }
 
-    /// Process Circle attestation for incoming CCTP transfer
-    pub fn process_cctp_attestation(
-        ctx: Context<ProcessCCTPAttestation>,
-        message_hash: Vec<u8>,
-        attestation: Vec<u8>,
+    /// Pay collected fees out of a mint's treasury (fee manager or admin)
+    pub fn withdraw_treasury<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawTreasury<'info>>,
+        amount: u64,
     ) -> Result<()> {
-        let cctp_tracker = &mut ctx.accounts.cctp_transfer_tracker;
-
-        // Verify attestation signature (simplified)
-        require!(!attestation.is_empty(), OmniVaultError::InvalidAttestation);
-
-        // Update transfer tracker
-        cctp_tracker.attestation_received = true;
-        cctp_tracker.attestation_timestamp = Clock::get()?.unix_timestamp;
-
-        msg!("CCTP attestation processed for message: {:?}", message_hash);
-
-        emit!(CCTPAttestationProcessed {
-            message_hash,
-            timestamp: Clock::get()?.unix_timestamp,
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        require!(
+            ctx.accounts.treasury_token_account.amount >= amount,
+            OmniVaultError::InsufficientBalance
+        );
+        
+        let fee_treasury = &ctx.accounts.fee_treasury;
+        let bump = &[fee_treasury.bump];
+        let treasury_seeds = &[
+            b"fee_treasury".as_ref(),
+            fee_treasury.mint.as_ref(),
+            bump,
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[treasury_seeds];
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.treasury_token_account.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.accounts.destination.to_account_info(),
+            fee_treasury.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
+        
+        emit!(TreasuryWithdrawn {
+            mint: fee_treasury.mint,
+            amount,
+            destination: ctx.accounts.destination.key(),
+            withdrawn_by: ctx.accounts.fee_manager.key(),
         });
-
+        
+        msg!("Withdrew {} from the {} fee treasury", amount, fee_treasury.mint);
         Ok(())
     }
-}
 
-// Helper function to convert CCTP domain to LayerZero chain ID
-fn cctp_domain_to_chain_id(domain: u32) -> u16 {
-    match domain {
-        0 => chain_ids::ETHEREUM,
-        1 => chain_ids::AVALANCHE,
-        2 => chain_ids::OPTIMISM,
-        3 => chain_ids::ARBITRUM,
-        6 => chain_ids::BASE,
-        7 => chain_ids::POLYGON,
-        _ => 0,
+    /// Accept an additional SPL mint into a vault with its own token account, minimum and cap (owner only)
+    pub fn add_vault_asset(ctx: Context<AddVaultAsset>, min_deposit: u64, deposit_cap: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let mint = ctx.accounts.asset_mint.key();
+        
+        require!(min_deposit > 0, OmniVaultError::InvalidAmount);
+        require!(mint != vault.asset_mint, OmniVaultError::AssetAlreadyAdded);
+        require!(vault.extra_assets.len() < MAX_VAULT_ASSETS, OmniVaultError::TooManyAssets);
+        ctx.accounts.vault_store.mint_tvl_mut(mint)?;
+        vault.extra_assets.push(mint);
+        
+        let vault_asset = &mut ctx.accounts.vault_asset;
+        vault_asset.vault = vault.key();
+        vault_asset.mint = mint;
+        vault_asset.token_account = ctx.accounts.asset_token_account.key();
+        vault_asset.min_deposit = min_deposit;
+        vault_asset.deposit_cap = deposit_cap;
+        vault_asset.total_deposits = 0;
+        vault_asset.total_yield = 0;
+        vault_asset.total_shares = 0;
+        vault_asset.accrued_fees = 0;
+        vault_asset.high_water_mark = PRICE_PRECISION;
+        vault_asset.last_fee_accrual = Clock::get()?.unix_timestamp;
+        vault_asset.bump = ctx.bumps.vault_asset;
+        
+        emit!(VaultAssetAdded {
+            vault_id: vault.id,
+            mint,
+            token_account: vault_asset.token_account,
+            min_deposit,
+            deposit_cap,
+        });
+        
+        msg!("Asset {} added to vault {}", mint, vault.id);
+        Ok(())
     }
-}
 
-// Helper function to create LayerZero options for different chains
-fn create_lz_options(dst_chain_id: u16) -> Result<Vec<u8>> {
-    // Create options based on destination chain
-    let gas_limit: u128 = match dst_chain_id {
-        101 => 200_000,    // Ethereum - higher gas
-        110 => 150_000,    // Arbitrum - lower gas
-        109 => 100_000,    // Polygon - very low gas
-        102 => 80_000,     // BSC - low gas
-        106 => 120_000,    // Avalanche - medium gas
-        111 => 150_000,    // Optimism - lower gas
-        _ => 100_000,      // Default
-    };
-    
-    // Simple options encoding - in production, use LayerZero SDK
-    let mut options = Vec::new();
-    options.extend_from_slice(&gas_limit.to_le_bytes());
-    options.push(0x01); // Version flag
-    
-    Ok(options)
-}
+    /// Deposit one of a vault's additional assets, priced against that asset's own NAV
+    pub fn deposit_asset<'info>(ctx: Context<'_, '_, 'info, 'info, DepositAsset<'info>>, amount: u64) -> Result<()> {
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        
+        let vault = &ctx.accounts.vault;
+        let vault_asset = &mut ctx.accounts.vault_asset;
+        let vault_store = &mut ctx.accounts.vault_store;
+        let asset_position = &mut ctx.accounts.asset_position;
+        
+        require!(amount >= vault_asset.min_deposit, OmniVaultError::DepositTooSmall);
+        require!(vault.is_active, OmniVaultError::VaultInactive);
+        require_not_paused(vault_store, vault, pause_flags::DEPOSITS)?;
+        require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
+        
+        // Price the deposit against the asset's NAV net of fees owed so far
+        let tvl_before = vault_asset.total_assets();
+        accrue_asset_fees(vault, vault_asset)?;
+        
+        let balance_before = ctx.accounts.asset_token_account.amount;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.asset_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[],
+        )?;
+        
+        // Credit only what arrived, net of any Token-2022 transfer fee
+        ctx.accounts.asset_token_account.reload()?;
+        let amount = ctx.accounts.asset_token_account.amount - balance_before;
+        let nav_before = vault_asset.total_assets();
+        let nav_after = nav_before.checked_add(amount).ok_or(OmniVaultError::MathOverflow)?;
+        if vault_asset.deposit_cap > 0 {
+            require!(nav_after <= vault_asset.deposit_cap, OmniVaultError::DepositCapExceeded);
+        }
+        check_global_deposit_cap(vault_store, vault_asset.mint, tvl_before, nav_after as u128)?;
+        let shares = shares_for_amount(amount, vault_asset.total_shares, nav_before)?;
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        
+        vault_asset.total_deposits += amount;
+        vault_asset.total_shares += shares;
+        sync_tvl(vault_store, vault_asset.mint, tvl_before, vault_asset.total_assets())?;
+        
+        if asset_position.user == Pubkey::default() {
+            asset_position.vault_asset = vault_asset.key();
+            asset_position.user = ctx.accounts.user.key();
+            asset_position.last_withdrawal = 0;
+            asset_position.bump = ctx.bumps.asset_position;
+        }
+        asset_position.shares += shares;
+        asset_position.amount += amount;
+        asset_position.last_deposit = Clock::get()?.unix_timestamp;
+        
+        emit!(AssetDepositMade {
+            vault_id: vault.id,
+            mint: vault_asset.mint,
+            user: ctx.accounts.user.key(),
+            amount,
+            shares,
+            total_assets: vault_asset.total_assets(),
+        });
+        
+        msg!("Deposited {} of asset {} to vault {} for {} shares", amount, vault_asset.mint, vault.id, shares);
+        Ok(())
+    }
 
-// Helper function to find the best chain based on risk profile
-fn find_best_chain<'a>(chain_yields: &'a [ChainYield], risk_profile: &RiskProfile) -> Option<&'a ChainYield> {
-    if chain_yields.is_empty() {
-        return None;
+    /// Withdraw one of a vault's additional assets by redeeming shares of that asset
+    pub fn withdraw_asset<'info>(ctx: Context<'_, '_, 'info, 'info, WithdrawAsset<'info>>, shares: u64) -> Result<()> {
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        
+        let vault = &ctx.accounts.vault;
+        let vault_asset = &mut ctx.accounts.vault_asset;
+        let vault_store = &mut ctx.accounts.vault_store;
+        let asset_position = &mut ctx.accounts.asset_position;
+        
+        require_not_paused(vault_store, vault, pause_flags::WITHDRAWALS)?;
+        require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
+        require!(asset_position.shares >= shares, OmniVaultError::InsufficientBalance);
+        
+        let nav_before = vault_asset.total_assets();
+        accrue_asset_fees(vault, vault_asset)?;
+        let amount = amount_for_shares(shares, vault_asset.total_shares, vault_asset.total_assets())?;
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        
+        // Exits inside the vault's lock window forfeit a decaying penalty to the asset's remaining holders
+        let now = Clock::get()?.unix_timestamp;
+        let penalty = early_exit_penalty(vault, asset_position.last_deposit, amount, now)?;
+        let fee = withdrawal_fee(amount - penalty, vault_store.fee_rate)?;
+        let withdrawal_amount = amount - penalty - fee;
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.asset_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            vault.to_account_info(),
+            ctx.remaining_accounts,
+            withdrawal_amount,
+            signer_seeds,
+        )?;
+        
+        vault_asset.debit_assets(amount)?;
+        vault_asset.total_yield += penalty;
+        vault_asset.total_shares -= shares;
+        vault_asset.accrued_fees += fee;
+        sync_tvl(vault_store, vault_asset.mint, nav_before, vault_asset.total_assets())?;
+        
+        asset_position.shares -= shares;
+        asset_position.amount = asset_position.amount.saturating_sub(amount);
+        asset_position.last_withdrawal = now;
+        
+        emit!(AssetWithdrawalMade {
+            vault_id: vault.id,
+            mint: vault_asset.mint,
+            user: ctx.accounts.user.key(),
+            amount: withdrawal_amount,
+            fee,
+            shares,
+            penalty,
+        });
+        
+        msg!("Withdrew {} of asset {} from vault {} (fee: {}, shares redeemed: {})", withdrawal_amount, vault_asset.mint, vault.id, fee, shares);
+        Ok(())
     }
-    
-    match risk_profile {
-        RiskProfile::Conservative => {
-            // Prioritize low risk, then yield
-            chain_yields
-                .iter()
-                .filter(|cy| cy.risk_score <= 30)
-                .max_by_key(|cy| cy.apy)
-        }
-        RiskProfile::Moderate => {
-            // Balance risk and yield
-            chain_yields
+
+    /// Return realized strategy yield in one of a vault's additional assets
+    pub fn harvest_asset_yield<'info>(ctx: Context<'_, '_, 'info, 'info, HarvestAssetYield<'info>>, amount: u64) -> Result<()> {
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+        
+        let vault_asset = &mut ctx.accounts.vault_asset;
+        let nav_before = vault_asset.total_assets();
+        
+        let balance_before = ctx.accounts.asset_token_account.amount;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.harvester_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.asset_token_account.to_account_info(),
+            ctx.accounts.harvester.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[],
+        )?;
+        ctx.accounts.asset_token_account.reload()?;
+        let amount = ctx.accounts.asset_token_account.amount - balance_before;
+        
+        accrue_asset_fees(&ctx.accounts.vault, vault_asset)?;
+        vault_asset.total_yield += amount;
+        // Charge the performance fee on the harvested yield right away
+        accrue_asset_fees(&ctx.accounts.vault, vault_asset)?;
+        sync_tvl(&mut ctx.accounts.vault_store, vault_asset.mint, nav_before, vault_asset.total_assets())?;
+        
+        emit!(AssetYieldHarvested {
+            vault_id: ctx.accounts.vault.id,
+            mint: vault_asset.mint,
+            amount,
+            total_yield: vault_asset.total_yield,
+            share_price: share_price(vault_asset.total_assets(), vault_asset.total_shares),
+        });
+        
+        msg!("Harvested {} of asset {} into vault {}", amount, vault_asset.mint, ctx.accounts.vault.id);
+        Ok(())
+    }
+
+    /// Move an additional asset's accrued withdrawal fees to its treasury (fee manager or admin)
+    pub fn collect_asset_fees<'info>(ctx: Context<'_, '_, 'info, 'info, CollectAssetFees<'info>>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let vault_asset = &mut ctx.accounts.vault_asset;
+        let fee_treasury = &mut ctx.accounts.fee_treasury;
+        
+        let nav_before = vault_asset.total_assets();
+        accrue_asset_fees(vault, vault_asset)?;
+        sync_tvl(&mut ctx.accounts.vault_store, vault_asset.mint, nav_before, vault_asset.total_assets())?;
+        let amount = vault_asset.accrued_fees;
+        require!(amount > 0, OmniVaultError::NoFeesToCollect);
+        
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.asset_token_account.to_account_info(),
+            &ctx.accounts.asset_mint,
+            ctx.accounts.treasury_token_account.to_account_info(),
+            vault.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
+        
+        vault_asset.accrued_fees = 0;
+        fee_treasury.total_collected += amount;
+        
+        emit!(FeesCollected {
+            vault_id: vault.id,
+            mint: fee_treasury.mint,
+            amount,
+            total_collected: fee_treasury.total_collected,
+            collected_by: ctx.accounts.fee_manager.key(),
+        });
+        
+        msg!("Collected {} in fees of asset {} from vault {}", amount, vault_asset.mint, vault.id);
+        Ok(())
+    }
+
+    /// Deposit USDC via CCTP from another chain.
+    /// Relays the attested burn message through the MessageTransmitter's receive_message
+    /// (accounts in remaining_accounts) and mints shares for the USDC that actually arrived.
+    /// The message must name the depositor as its destination caller.
+    pub fn deposit_usdc_via_cctp<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositUSDCViaCCTP<'info>>,
+        message: Vec<u8>,
+        attestation: Vec<u8>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let vault_store = &mut ctx.accounts.vault_store;
+        let cctp_config = &ctx.accounts.cctp_config;
+
+        // Verify system is not paused
+        require_not_paused(vault_store, vault, pause_flags::DEPOSITS | pause_flags::CCTP)?;
+        require!(vault.is_active, OmniVaultError::VaultInactive);
+
+        // Only the depositor named in the message may relay it, so nobody else can claim its shares
+        require!(!attestation.is_empty(), OmniVaultError::InvalidAttestation);
+        require!(message.len() >= CCTP_MESSAGE_HEADER_LEN, OmniVaultError::InvalidAttestation);
+        let source_domain = u32::from_be_bytes(message[4..8].try_into().unwrap());
+        require!(
+            cctp_config.supported_domains.contains(&source_domain),
+            OmniVaultError::DomainNotSupported
+        );
+        let destination_caller = &message[CCTP_DESTINATION_CALLER_OFFSET..CCTP_MESSAGE_HEADER_LEN];
+        require!(
+            destination_caller == ctx.accounts.user.key().as_ref(),
+            OmniVaultError::UnauthorizedCaller
+        );
+
+        // The MessageTransmitter verifies the attestation and mints USDC to the vault
+        let balance_before = ctx.accounts.vault_usdc_account.amount;
+        let mut data = hash(b"global:receive_message").to_bytes()[..8].to_vec();
+        ReceiveMessageParams { message, attestation }.serialize(&mut data)?;
+        let receive_instruction = Instruction {
+            program_id: ctx.accounts.message_transmitter.key(),
+            accounts: ctx
+                .remaining_accounts
                 .iter()
-                .max_by_key(|cy| cy.apy.saturating_sub(cy.risk_score * 2))
+                .map(|account| AccountMeta {
+                    pubkey: account.key(),
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data,
+        };
+        let mut receive_accounts = ctx.remaining_accounts.to_vec();
+        receive_accounts.push(ctx.accounts.message_transmitter.to_account_info());
+        invoke(&receive_instruction, &receive_accounts)?;
+        ctx.accounts.vault_usdc_account.reload()?;
+        let amount = ctx.accounts.vault_usdc_account.amount
+            .checked_sub(balance_before)
+            .ok_or(OmniVaultError::InvalidAmount)?;
+
+        // Verify amount meets minimum deposit
+        require!(amount >= vault.min_deposit, OmniVaultError::DepositTooSmall);
+
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        check_deposit_caps(vault, vault_store, nav_before, amount)?;
+        let shares = shares_for_amount(amount, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+
+        // Mint vault shares for the bridged amount
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.user_share_account.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let signer_seeds: &[&[&[u8]]] = &[vault_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, shares)?;
+
+        // Update user position
+        let user_position = &mut ctx.accounts.user_position;
+        user_position.amount += amount;
+        user_position.record_deposit(vault, ctx.accounts.user_share_account.amount, shares, Clock::get()?.unix_timestamp);
+        if user_position.user == Pubkey::default() {
+            user_position.user = ctx.accounts.user.key();
+            user_position.vault = vault.key();
+            user_position.last_withdrawal = 0;
+            user_position.bump = ctx.bumps.user_position;
+            user_position.version = USER_POSITION_VERSION;
         }
-        RiskProfile::Aggressive => {
-            // Prioritize highest yield
-            chain_yields.iter().max_by_key(|cy| cy.apy)
+
+        // Update vault totals
+        vault.total_deposits += amount;
+        vault.update_share_price(ctx.accounts.share_mint.supply + shares);
+        sync_tvl(vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+
+        // Check if automatic yield optimization should trigger
+        if vault.total_deposits > vault.rebalance_threshold {
+            msg!("Deposit threshold reached, consider rebalancing");
         }
+
+        emit!(CCTPDepositMade {
+            vault_id: vault.id,
+            user: ctx.accounts.user.key(),
+            amount,
+            source_domain,
+            new_total: vault.total_deposits,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw USDC via CCTP to another chain by burning vault shares
+    ///
+    /// Expects the TokenMessengerMinter deposit_for_burn accounts after its owner as remaining
+    /// accounts. The vault signs as owner of the burned token account; the event data account
+    /// must sign the transaction.
+    pub fn withdraw_usdc_via_cctp<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawUSDCViaCCTP<'info>>,
+        shares: u64,
+        destination_domain: u32,
+        destination_address: Vec<u8>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let vault_store = &mut ctx.accounts.vault_store;
+        let user_position = &mut ctx.accounts.user_position;
+        let cctp_config = &ctx.accounts.cctp_config;
+
+        // Verify system is not paused
+        require_not_paused(vault_store, vault, pause_flags::WITHDRAWALS | pause_flags::CCTP)?;
+        require!(!vault.emergency_exit, OmniVaultError::VaultEmergencyExit);
+
+        // Check sufficient balance
+        require!(shares > 0, OmniVaultError::InvalidAmount);
+        require!(ctx.accounts.user_share_account.amount >= shares, OmniVaultError::InsufficientBalance);
+        require!(
+            cctp_config.supported_domains.contains(&destination_domain),
+            OmniVaultError::DomainNotSupported
+        );
+        let mint_recipient = Pubkey::try_from(destination_address.as_slice())
+            .map_err(|_| OmniVaultError::InvalidMintRecipient)?;
+
+        let nav_before = vault.total_assets();
+        accrue_fees(vault, ctx.accounts.share_mint.supply)?;
+        let amount = amount_for_shares(shares, ctx.accounts.share_mint.supply, vault.total_assets())?;
+        require!(amount > 0, OmniVaultError::InvalidAmount);
+
+        // Exits inside the lock window forfeit a decaying penalty to remaining holders
+        let now = Clock::get()?.unix_timestamp;
+        let balance = ctx.accounts.user_share_account.amount;
+        let penalty = user_position.redeem_shares(vault, balance, shares, amount, now)?;
+
+        // Calculate withdrawal fee (1% for CCTP Fast Transfer)
+        let fee = u64::try_from(
+            (amount.checked_sub(penalty).ok_or(OmniVaultError::MathOverflow)? as u128)
+                * cctp_config.fee_rate as u128
+                / 10000,
+        )
+        .map_err(|_| OmniVaultError::MathOverflow)?;
+        let net_amount = penalty
+            .checked_add(fee)
+            .and_then(|kept| amount.checked_sub(kept))
+            .ok_or(OmniVaultError::MathOverflow)?;
+
+        // Only the net amount leaves the vault, and never out of reserved or fee funds
+        require!(
+            token_liquidity(vault, ctx.accounts.vault_usdc_account.amount) >= net_amount,
+            OmniVaultError::InsufficientLiquidity
+        );
+
+        // Burn the redeemed shares
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.user_share_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token_interface::burn(cpi_ctx, shares)?;
+
+        // Update user position
+        user_position.amount = user_position.amount.saturating_sub(amount);
+        user_position.last_withdrawal = now;
+
+        // Update vault totals
+        // The fast transfer fee stays in the vault as yield for remaining holders
+        vault.debit_exit(net_amount + penalty, penalty)?;
+        vault.update_share_price(ctx.accounts.share_mint.supply - shares);
+        sync_tvl(vault_store, vault.asset_mint, nav_before, vault.total_assets())?;
+
+        // Burn the net USDC from the vault through CCTP, minted to the recipient on the destination domain
+        let bump = &[vault.bump];
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.creator.as_ref(),
+            &vault.id.to_le_bytes(),
+            bump,
+        ];
+        cctp_deposit_for_burn(
+            &ctx.accounts.token_messenger,
+            &vault.to_account_info(),
+            ctx.remaining_accounts,
+            DepositForBurnParams {
+                amount: net_amount,
+                destination_domain,
+                mint_recipient,
+            },
+            &[vault_seeds],
+        )?;
+        msg!("CCTP burn of {} USDC to domain {} (recipient: {})", net_amount, destination_domain, mint_recipient);
+
+        emit!(CCTPWithdrawalMade {
+            vault_id: vault.id,
+            user: ctx.accounts.user.key(),
+            amount: net_amount,
+            fee,
+            destination_domain,
+            shares,
+            penalty,
+        });
+
+        Ok(())
     }
+
+    /// Rebalance vault using CCTP Fast Transfer
+    pub fn rebalance_with_cctp(
+        ctx: Context<RebalanceWithCCTP>,
+        target_domain: u32,
+        amount: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let yield_tracker = &mut ctx.accounts.yield_tracker;
+
+        require_not_paused(&ctx.accounts.vault_store, vault, pause_flags::REBALANCE | pause_flags::CCTP)?;
+
+        // Verify rebalancing is not too frequent
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time - vault.last_rebalance >= MIN_REBALANCE_INTERVAL,
+            OmniVaultError::RebalanceTooFrequent
+        );
+
+        // Find the chain yield data for target domain
+        let chain_id = cctp_domain_to_chain_id(target_domain);
+        let target_yield = yield_tracker.chain_yields
+            .iter()
+            .find(|cy| cy.chain_id == chain_id)
+            .ok_or(OmniVaultError::InvalidTargetChain)?;
+
+        // Verify rebalancing improves yield
+        require!(
+            target_yield.apy > vault.current_apy,
+            OmniVaultError::NoYieldImprovement
+        );
+
+        let yield_improvement = target_yield.apy - vault.current_apy;
+
+        // Update vault state
+        vault.last_rebalance = current_time;
+        vault.current_best_chain = chain_id;
+        vault.current_apy = target_yield.apy;
+
+        msg!("Rebalancing {} USDC to domain {} via CCTP Fast Transfer", amount, target_domain);
+
+        emit!(CCTPRebalanceExecuted {
+            vault_id: vault.id,
+            amount,
+            from_chain: vault.current_best_chain,
+            to_domain: target_domain,
+            yield_improvement,
+            is_fast_transfer: true,
+        });
+
+        Ok(())
+    }
+
+    /// Process incoming CCTP hook for automated actions
+    pub fn handle_cctp_hook(
+        ctx: Context<HandleCCTPHook>,
+        hook_data: Vec<u8>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        require_not_paused(&ctx.accounts.vault_store, vault, pause_flags::CCTP)?;
+
+        // Parse hook data to determine action
+        require!(!hook_data.is_empty(), OmniVaultError::InvalidHookData);
+
+        let action_type = hook_data[0];
+
+        match action_type {
+            1 => { // Deposit to vault
+                msg!("Processing automated vault deposit via CCTP hook");
+                // Process deposit logic
+            },
+            2 => { // Rebalance
+                msg!("Processing automated rebalance via CCTP hook");
+                // Process rebalance logic
+            },
+            3 => { // Compound yield
+                msg!("Processing yield compounding via CCTP hook");
+                // Process compound logic
+            },
+            _ => {
+                return Err(OmniVaultError::UnsupportedHookAction.into());
+            }
+        }
+
+        emit!(CCTPHookExecuted {
+            vault_id: vault.id,
+            action_type,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Process Circle attestation for incoming CCTP transfer
+    pub fn process_cctp_attestation(
+        ctx: Context<ProcessCCTPAttestation>,
+        message_hash: Vec<u8>,
+        attestation: Vec<u8>,
+    ) -> Result<()> {
+        let cctp_tracker = &mut ctx.accounts.cctp_transfer_tracker;
+        let vault_store = &ctx.accounts.vault_store;
+
+        require!(!vault_store.emergency_pause, OmniVaultError::SystemPaused);
+        require!(vault_store.pause_flags & pause_flags::CCTP == 0, OmniVaultError::OperationPaused);
+
+        // Verify attestation signature (simplified)
+        require!(!attestation.is_empty(), OmniVaultError::InvalidAttestation);
+
+        // Update transfer tracker
+        cctp_tracker.attestation_received = true;
+        cctp_tracker.attestation_timestamp = Clock::get()?.unix_timestamp;
+
+        msg!("CCTP attestation processed for message: {:?}", message_hash);
+
+        emit!(CCTPAttestationProcessed {
+            message_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to convert CCTP domain to LayerZero chain ID
+fn cctp_domain_to_chain_id(domain: u32) -> u16 {
+    match domain {
+        0 => chain_ids::ETHEREUM,
+        1 => chain_ids::AVALANCHE,
+        2 => chain_ids::OPTIMISM,
+        3 => chain_ids::ARBITRUM,
+        6 => chain_ids::BASE,
+        7 => chain_ids::POLYGON,
+        _ => 0,
+    }
+}
+
+// Helper function to price a deposit in vault shares
+fn shares_for_amount(amount: u64, total_shares: u64, total_assets: u64) -> Result<u64> {
+    if total_shares == 0 || total_assets == 0 {
+        // First deposit (or a fully drained vault) mints shares 1:1
+        return Ok(amount);
+    }
+    let shares = (amount as u128)
+        .checked_mul(total_shares as u128)
+        .ok_or(OmniVaultError::MathOverflow)?
+        / total_assets as u128;
+    u64::try_from(shares).map_err(|_| OmniVaultError::MathOverflow.into())
+}
+
+// Helper function to price a share redemption in the underlying asset
+fn amount_for_shares(shares: u64, total_shares: u64, total_assets: u64) -> Result<u64> {
+    require!(shares <= total_shares, OmniVaultError::InsufficientBalance);
+    let amount = (shares as u128)
+        .checked_mul(total_assets as u128)
+        .ok_or(OmniVaultError::MathOverflow)?
+        / total_shares as u128;
+    u64::try_from(amount).map_err(|_| OmniVaultError::MathOverflow.into())
+}
+
+// Helper function to compute the withdrawal fee on `amount` at `fee_rate` basis points
+fn withdrawal_fee(amount: u64, fee_rate: u16) -> Result<u64> {
+    u64::try_from(amount as u128 * fee_rate as u128 / 10000).map_err(|_| OmniVaultError::MathOverflow.into())
+}
+
+// Helper function to compute the exchange rate of one share in asset units
+fn share_price(total_assets: u64, total_shares: u64) -> u64 {
+    if total_shares == 0 {
+        return PRICE_PRECISION;
+    }
+    let price = total_assets as u128 * PRICE_PRECISION as u128 / total_shares as u128;
+    u64::try_from(price).unwrap_or(u64::MAX)
+}
+
+// Helper function to validate a multisig signer set
+fn validate_multisig_signers(signers: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(
+        !signers.is_empty() && signers.len() <= MAX_MULTISIG_SIGNERS,
+        OmniVaultError::InvalidMultisigSigners
+    );
+    require!(
+        threshold > 0 && threshold as usize <= signers.len(),
+        OmniVaultError::InvalidThreshold
+    );
+    for (i, signer) in signers.iter().enumerate() {
+        require!(!signers[..i].contains(signer), OmniVaultError::InvalidMultisigSigners);
+    }
+    Ok(())
+}
+
+// Helper function to validate a configuration change against current store parameters
+fn validate_config_change(change: &ConfigChange, vault_store: &VaultStore) -> Result<()> {
+    match change {
+        ConfigChange::Vault {
+            min_deposit,
+            rebalance_threshold,
+            target_chains,
+            management_fee_bps,
+            performance_fee_bps,
+            lock_period,
+            early_exit_penalty_bps,
+            ..
+        } => {
+            if let Some(min_deposit) = min_deposit {
+                require!(*min_deposit > 0, OmniVaultError::InvalidAmount);
+            }
+            if let Some(threshold) = rebalance_threshold {
+                require!(*threshold > 0 && *threshold <= 1000, OmniVaultError::InvalidThreshold); // Max 10%
+            }
+            if let Some(chains) = target_chains {
+                require!(!chains.is_empty() && chains.len() <= 10, OmniVaultError::InvalidChainConfiguration);
+                require!(
+                    chains.iter().all(|chain| vault_store.supported_chains.contains(chain)),
+                    OmniVaultError::UnsupportedChain
+                );
+            }
+            if let Some(fee) = management_fee_bps {
+                require!(*fee <= MAX_MANAGEMENT_FEE_BPS, OmniVaultError::FeeTooHigh);
+            }
+            if let Some(fee) = performance_fee_bps {
+                require!(*fee <= MAX_PERFORMANCE_FEE_BPS, OmniVaultError::FeeTooHigh);
+            }
+            if let Some(lock_period) = lock_period {
+                require!((0..=MAX_LOCK_PERIOD).contains(lock_period), OmniVaultError::InvalidLockPeriod);
+            }
+            if let Some(penalty) = early_exit_penalty_bps {
+                require!(*penalty <= MAX_EARLY_EXIT_PENALTY_BPS, OmniVaultError::FeeTooHigh);
+            }
+        }
+        ConfigChange::Store {
+            fee_rate,
+            add_chains,
+            remove_chains,
+            config_delay,
+            global_deposit_cap,
+        } => {
+            if let Some(global_deposit_cap) = global_deposit_cap {
+                require!(
+                    vault_store.mint_tvl(&global_deposit_cap.mint).is_some()
+                        || vault_store.mint_tvls.len() < MAX_TVL_MINTS,
+                    OmniVaultError::TooManyAssets
+                );
+            }
+            if let Some(fee_rate) = fee_rate {
+                require!(*fee_rate <= MAX_WITHDRAWAL_FEE_RATE, OmniVaultError::FeeTooHigh);
+            }
+            if let Some(delay) = config_delay {
+                require!(
+                    (MIN_CONFIG_DELAY..=MAX_CONFIG_DELAY).contains(delay),
+                    OmniVaultError::InvalidTimelockDelay
+                );
+            }
+            require!(!add_chains.contains(&0), OmniVaultError::InvalidChainConfiguration);
+            let mut chains: Vec<u16> = vault_store.supported_chains
+                .iter()
+                .filter(|chain| !remove_chains.contains(chain))
+                .copied()
+                .collect();
+            for chain in add_chains {
+                if !chains.contains(chain) {
+                    chains.push(*chain);
+                }
+            }
+            require!(chains.len() <= MAX_SUPPORTED_CHAINS, OmniVaultError::TooManyChains);
+        }
+    }
+    Ok(())
+}
+
+// Helper function to accrue management and performance fees up to the current time.
+// Fees are carved out of NAV into Vault.accrued_fees for collection by the treasury.
+fn accrue_fees(vault: &mut Vault, total_shares: u64) -> Result<()> {
+    accrue_fees_at(vault, total_shares, Clock::get()?.unix_timestamp)
+}
+
+fn accrue_fees_at(vault: &mut Vault, total_shares: u64, now: i64) -> Result<()> {
+    let elapsed = now.saturating_sub(vault.last_fee_accrual).max(0);
+    
+    if total_shares == 0 {
+        vault.last_fee_accrual = now;
+        vault.high_water_mark = vault.high_water_mark.max(PRICE_PRECISION);
+        return Ok(());
+    }
+    
+    // Management fee: pro-rated annual charge on total assets
+    let management_fee = u64::try_from(
+        vault.total_assets() as u128 * vault.management_fee_bps as u128 * elapsed as u128
+            / (10000 * SECONDS_PER_YEAR as u128),
+    )
+    .map_err(|_| OmniVaultError::MathOverflow)?;
+    // Keep the clock running while the charge still rounds to zero, so frequent
+    // interactions cannot truncate the fee away
+    if management_fee > 0 || vault.management_fee_bps == 0 || vault.total_assets() == 0 {
+        vault.last_fee_accrual = now;
+    }
+    vault.debit_assets(management_fee)?;
+    
+    // Performance fee: only on gains above the high-water mark
+    let mut performance_fee = 0;
+    let price = share_price(vault.total_assets(), total_shares);
+    if price > vault.high_water_mark && vault.performance_fee_bps > 0 {
+        let gain = (price - vault.high_water_mark) as u128 * total_shares as u128 / PRICE_PRECISION as u128;
+        performance_fee = u64::try_from(gain * vault.performance_fee_bps as u128 / 10000)
+            .map_err(|_| OmniVaultError::MathOverflow)?
+            .min(vault.total_yield);
+        vault.total_yield -= performance_fee;
+    }
+    
+    vault.accrued_fees = vault.accrued_fees
+        .checked_add(management_fee + performance_fee)
+        .ok_or(OmniVaultError::MathOverflow)?;
+    vault.update_share_price(total_shares);
+    vault.high_water_mark = vault.high_water_mark.max(vault.share_price);
+    
+    if management_fee > 0 || performance_fee > 0 {
+        emit!(FeesAccrued {
+            vault_id: vault.id,
+            management_fee,
+            performance_fee,
+            high_water_mark: vault.high_water_mark,
+            share_price: vault.share_price,
+            timestamp: now,
+        });
+    }
+    
+    Ok(())
+}
+
+// Helper function to accrue the vault's management and performance fees on one of its
+// additional assets. Same schedule as accrue_fees, measured against the asset's own NAV,
+// share count, high-water mark and clock. Fees go to VaultAsset.accrued_fees.
+fn accrue_asset_fees(vault: &Vault, vault_asset: &mut VaultAsset) -> Result<()> {
+    accrue_asset_fees_at(vault, vault_asset, Clock::get()?.unix_timestamp)
+}
+
+fn accrue_asset_fees_at(vault: &Vault, vault_asset: &mut VaultAsset, now: i64) -> Result<()> {
+    let elapsed = now.saturating_sub(vault_asset.last_fee_accrual).max(0);
+    let total_shares = vault_asset.total_shares;
+    
+    if total_shares == 0 {
+        vault_asset.last_fee_accrual = now;
+        vault_asset.high_water_mark = vault_asset.high_water_mark.max(PRICE_PRECISION);
+        return Ok(());
+    }
+    
+    // Management fee: pro-rated annual charge on the asset's NAV
+    let management_fee = u64::try_from(
+        vault_asset.total_assets() as u128 * vault.management_fee_bps as u128 * elapsed as u128
+            / (10000 * SECONDS_PER_YEAR as u128),
+    )
+    .map_err(|_| OmniVaultError::MathOverflow)?;
+    if management_fee > 0 || vault.management_fee_bps == 0 || vault_asset.total_assets() == 0 {
+        vault_asset.last_fee_accrual = now;
+    }
+    vault_asset.debit_assets(management_fee)?;
+    
+    // Performance fee: only on gains above the asset's high-water mark
+    let mut performance_fee = 0;
+    let price = share_price(vault_asset.total_assets(), total_shares);
+    if price > vault_asset.high_water_mark && vault.performance_fee_bps > 0 {
+        let gain = (price - vault_asset.high_water_mark) as u128 * total_shares as u128 / PRICE_PRECISION as u128;
+        performance_fee = u64::try_from(gain * vault.performance_fee_bps as u128 / 10000)
+            .map_err(|_| OmniVaultError::MathOverflow)?
+            .min(vault_asset.total_yield);
+        vault_asset.total_yield -= performance_fee;
+    }
+    
+    vault_asset.accrued_fees = vault_asset.accrued_fees
+        .checked_add(management_fee + performance_fee)
+        .ok_or(OmniVaultError::MathOverflow)?;
+    let price = share_price(vault_asset.total_assets(), total_shares);
+    vault_asset.high_water_mark = vault_asset.high_water_mark.max(price);
+    
+    if management_fee > 0 || performance_fee > 0 {
+        emit!(AssetFeesAccrued {
+            vault_id: vault.id,
+            mint: vault_asset.mint,
+            management_fee,
+            performance_fee,
+            high_water_mark: vault_asset.high_water_mark,
+            share_price: price,
+            timestamp: now,
+        });
+    }
+    
+    Ok(())
+}
+
+// Helper function to price an exit inside a vault's lock window. The penalty starts at
+// early_exit_penalty_bps at the deposit time and decays linearly to zero at lock_period.
+fn early_exit_penalty(vault: &Vault, last_deposit: i64, amount: u64, now: i64) -> Result<u64> {
+    let elapsed = now.saturating_sub(last_deposit);
+    if vault.lock_period == 0 || elapsed >= vault.lock_period {
+        return Ok(0);
+    }
+    // Without a penalty schedule the lock is a hard lock
+    require!(vault.early_exit_penalty_bps > 0, OmniVaultError::PositionLocked);
+    let remaining = (vault.lock_period - elapsed) as u128;
+    u64::try_from(
+        amount as u128 * vault.early_exit_penalty_bps as u128 * remaining
+            / (10000 * vault.lock_period as u128),
+    )
+    .map_err(|_| OmniVaultError::MathOverflow.into())
+}
+
+// Helper function to measure tokens an SPL vault can pay out: its balance less claimable
+// queued withdrawals, fees awaiting collection and funds set aside for emergency claims
+fn token_liquidity(vault: &Vault, balance: u64) -> u64 {
+    balance
+        .saturating_sub(vault.reserved_withdrawals)
+        .saturating_sub(vault.accrued_fees)
+        .saturating_sub(vault.emergency_reserved)
+}
+
+// Helper function to measure what a fee sweep may take out of `available` (token balance, or
+// lamports above rent): everything not set aside for queued withdrawals or emergency claims
+fn fee_liquidity(vault: &Vault, available: u64) -> u64 {
+    available
+        .saturating_sub(vault.reserved_withdrawals)
+        .saturating_sub(vault.emergency_reserved)
+}
+
+// Helper function to size a queued fill: all escrowed shares when liquidity covers the net
+// payout, otherwise the share of them that liquidity does cover
+fn fillable_shares(shares: u64, payout: u64, liquidity: u64) -> u64 {
+    if liquidity >= payout {
+        return shares;
+    }
+    (shares as u128 * liquidity as u128 / payout as u128) as u64
+}
+
+// Helper function to measure lamports a SOL vault can pay out above rent, accrued fees and
+// funds set aside for emergency claims
+fn sol_liquidity(vault_info: &AccountInfo, vault: &Vault) -> Result<u64> {
+    let rent_floor = Rent::get()?.minimum_balance(vault_info.data_len());
+    Ok(vault_info
+        .lamports()
+        .saturating_sub(rent_floor)
+        .saturating_sub(vault.accrued_fees)
+        .saturating_sub(vault.emergency_reserved))
+}
+
+// Helper function to settle an emergency claim for redemption and return what it can take now.
+// In emergency mode that is its part of liquidity recovered since its last checkpoint; once the
+// vault is out of emergency mode the rest of the claim is repaid at par from free liquidity.
+fn emergency_redemption(vault: &mut Vault, claim: &mut EmergencyClaim, liquidity: u64) -> Result<u64> {
+    if vault.emergency_exit {
+        vault.sync_emergency_liquidity(liquidity)?;
+    }
+    claim.settle(vault);
+    if !vault.emergency_exit && claim.amount > 0 {
+        let repaid = claim.amount.min(liquidity);
+        let units = claim.units * repaid as u128 / claim.amount as u128;
+        claim.amount -= repaid;
+        claim.units -= units;
+        claim.pending += repaid;
+        vault.emergency_claims = vault.emergency_claims.saturating_sub(repaid);
+        vault.emergency_claim_units = vault.emergency_claim_units.saturating_sub(units);
+        vault.emergency_reserved += repaid;
+        vault.retire_repaid_claims();
+    }
+    // Rounding can leave a claim a few units above what was set aside for it
+    let paid = claim.pending.min(vault.emergency_reserved);
+    claim.pending -= paid;
+    vault.emergency_reserved -= paid;
+    Ok(paid)
+}
+
+// Helper function to move tokens with transfer_checked under SPL Token or Token-2022.
+// Any extra accounts a transfer-hook mint needs are forwarded from remaining_accounts.
+#[allow(clippy::too_many_arguments)]
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_transfer_checked(
+        token_program.key,
+        from,
+        mint.to_account_info(),
+        to,
+        authority,
+        remaining_accounts,
+        amount,
+        mint.decimals,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+// Helper function to reject deposits that would push the vault or the store past its cap.
+// Called after fee accrual, so total_tvl is adjusted by the vault's NAV change since nav_before.
+fn check_deposit_caps(vault: &Vault, vault_store: &VaultStore, nav_before: u64, amount: u64) -> Result<()> {
+    let vault_nav = (vault.total_assets() as u128) + amount as u128;
+    if vault.deposit_cap > 0 {
+        require!(vault_nav <= vault.deposit_cap as u128, OmniVaultError::DepositCapExceeded);
+    }
+    check_global_deposit_cap(vault_store, vault.asset_mint, nav_before, vault_nav)
+}
+
+// Helper function to enforce the store-wide cap on a mint's TVL for a vault moving from
+// nav_before to nav_after
+fn check_global_deposit_cap(vault_store: &VaultStore, mint: Pubkey, nav_before: u64, nav_after: u128) -> Result<()> {
+    if let Some(mint_tvl) = vault_store.mint_tvl(&mint).filter(|mint_tvl| mint_tvl.deposit_cap > 0) {
+        let total_tvl = (mint_tvl.total_tvl.saturating_sub(nav_before) as u128) + nav_after;
+        require!(
+            total_tvl <= mint_tvl.deposit_cap as u128,
+            OmniVaultError::GlobalDepositCapExceeded
+        );
+    }
+    Ok(())
+}
+
+// Helper function to fold a change in one vault's NAV into the store-wide TVL of its mint.
+// Native SOL and wSOL vaults share the wSOL mint, both being denominated in lamports.
+fn sync_tvl(vault_store: &mut VaultStore, mint: Pubkey, nav_before: u64, nav_after: u64) -> Result<()> {
+    if nav_before == nav_after {
+        return Ok(());
+    }
+    let mint_tvl = vault_store.mint_tvl_mut(mint)?;
+    mint_tvl.total_tvl = mint_tvl.total_tvl.saturating_sub(nav_before).saturating_add(nav_after);
+    Ok(())
+}
+
+// Helper function to reject an operation when the store is paused or either level pauses one of `flags`
+fn require_not_paused(vault_store: &VaultStore, vault: &Vault, flags: u8) -> Result<()> {
+    require!(!vault_store.emergency_pause, OmniVaultError::SystemPaused);
+    require!((vault_store.pause_flags | vault.pause_flags) & flags == 0, OmniVaultError::OperationPaused);
+    Ok(())
+}
+
+// Helper function to close the vault's InboundNonce accounts among `accounts`, returning the rest.
+// Each one must be the vault's nonce PDA for the chain it records.
+fn close_inbound_nonces<'info>(
+    vault: Pubkey,
+    accounts: &[AccountInfo<'info>],
+    rent_receiver: AccountInfo<'info>,
+) -> Result<Vec<AccountInfo<'info>>> {
+    let mut rest = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        let is_nonce = account.owner == &crate::ID
+            && account.try_borrow_data()?.starts_with(InboundNonce::DISCRIMINATOR);
+        if !is_nonce {
+            rest.push(account.clone());
+            continue;
+        }
+        let inbound_nonce = InboundNonce::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        let expected = Pubkey::create_program_address(
+            &[
+                b"inbound_nonce",
+                vault.as_ref(),
+                &inbound_nonce.src_chain_id.to_le_bytes(),
+                &[inbound_nonce.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| OmniVaultError::InvalidVaultId)?;
+        require!(
+            inbound_nonce.vault == vault && account.key() == expected,
+            OmniVaultError::InvalidVaultId
+        );
+        close_program_account(account, &rent_receiver)?;
+    }
+    Ok(rest)
+}
+
+// Helper function to close an account owned by this program, moving its rent to `rent_receiver`
+fn close_program_account(account: &AccountInfo, rent_receiver: &AccountInfo) -> Result<()> {
+    let lamports = rent_receiver
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(OmniVaultError::MathOverflow)?;
+    **rent_receiver.try_borrow_mut_lamports()? = lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.assign(&system_program::ID);
+    account.resize(0)?;
+    Ok(())
+}
+
+// Helper function to hand any residual balance of a vault-owned token account to `recipient` and close it.
+// Native accounts release their wrapped lamports on close, so they need no recipient.
+#[allow(clippy::too_many_arguments)]
+fn sweep_and_close<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    recipient: Option<&InterfaceAccount<'info, TokenAccount>>,
+    rent_receiver: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if token_account.amount > 0 && mint.key() != native_mint::id() {
+        let recipient = recipient.ok_or(OmniVaultError::SweepAccountRequired)?;
+        transfer_tokens(
+            token_program,
+            token_account.to_account_info(),
+            mint,
+            recipient.to_account_info(),
+            authority.clone(),
+            remaining_accounts,
+            token_account.amount,
+            signer_seeds,
+        )?;
+    }
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: token_account.to_account_info(),
+            destination: rent_receiver,
+            authority,
+        },
+        signer_seeds,
+    ))
+}
+
+// Helper function to grow a legacy account to `space` bytes, topping up its rent from `payer`.
+// New bytes are zeroed, so appended fields deserialize as zero until the migration back-fills them.
+fn resize_for_migration<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    if account.data_len() >= space {
+        return Ok(());
+    }
+    let rent_due = Rent::get()?.minimum_balance(space).saturating_sub(account.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    account.resize(space)?;
+    Ok(())
+}
+
+// Helper function to build the yield query message sent to every target chain
+fn yield_query_message(vault: &Account<Vault>, query_nonce: u64, target_chains: &[u16], timestamp: i64) -> CrossChainMessage {
+    CrossChainMessage {
+        action: CrossChainAction::YieldQuery {
+            vault_id: vault.id,
+            vault: vault.key(),
+            risk_profile: vault.risk_profile.clone(),
+            query_nonce,
+            requested_chains: target_chains.to_vec(),
+        },
+        timestamp,
+        nonce: query_nonce,
+    }
+}
+
+// Helper function to resolve each target chain's endpoint id, executor options and peer from
+// its ChainConfig and Peer, passed in the same order as remaining accounts. The rest of a
+// chain's accounts are the endpoint's quote accounts, then its send accounts from the OApp on.
+fn load_chain_sends<'info>(
+    target_chains: &[u16],
+    oapp: Pubkey,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<Vec<ChainSend<'info>>> {
+    // Each chain's group of accounts starts at its ChainConfig
+    let is_chain_config = |account: &AccountInfo| {
+        account.owner == &crate::ID
+            && account.try_borrow_data().is_ok_and(|data| data.starts_with(ChainConfig::DISCRIMINATOR))
+    };
+    let mut starts: Vec<usize> = (0..remaining_accounts.len())
+        .filter(|index| is_chain_config(&remaining_accounts[*index]))
+        .collect();
+    require!(
+        starts.len() == target_chains.len() && starts.first() == Some(&0),
+        OmniVaultError::MissingChainConfig
+    );
+    starts.push(remaining_accounts.len());
+    
+    target_chains
+        .iter()
+        .zip(starts.windows(2))
+        .map(|(chain_id, bounds)| {
+            let group = &remaining_accounts[bounds[0]..bounds[1]];
+            require!(group.len() >= 2, OmniVaultError::MissingChainConfig);
+            let chain_config = Account::<ChainConfig>::try_from(&group[0])?;
+            require!(chain_config.chain_id == *chain_id, OmniVaultError::MissingChainConfig);
+            let peer = Account::<Peer>::try_from(&group[1])?;
+            require!(peer.chain_id == *chain_id, OmniVaultError::InvalidPeer);
+            let send_start = group[2..]
+                .iter()
+                .position(|account| account.key() == oapp)
+                .map_or(group.len(), |index| index + 2);
+            Ok(ChainSend {
+                chain_id: *chain_id,
+                eid: chain_config.eid,
+                options: chain_config.executor_options.encode(),
+                receiver: peer.address,
+                quote_accounts: &group[2..send_start],
+                send_accounts: &group[send_start..],
+            })
+        })
+        .collect()
+}
+
+// One destination of a cross-chain send, resolved from remaining accounts
+struct ChainSend<'info> {
+    chain_id: u16,
+    eid: u32, // LayerZero V2 endpoint id of chain_id
+    options: Vec<u8>,
+    receiver: [u8; 32],
+    quote_accounts: &'info [AccountInfo<'info>], // Send library and endpoint accounts for quote
+    send_accounts: &'info [AccountInfo<'info>], // Endpoint accounts for send, starting with the OApp
+}
+
+// Helper function to burn USDC held by `owner` through the CCTP TokenMessengerMinter. The rest
+// of the deposit_for_burn accounts (burn token account, CCTP state, event data and programs)
+// come in `burn_accounts`.
+fn cctp_deposit_for_burn<'info>(
+    token_messenger: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    burn_accounts: &[AccountInfo<'info>],
+    params: DepositForBurnParams,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = hash(b"global:deposit_for_burn").to_bytes()[..8].to_vec();
+    params.serialize(&mut data)?;
+    let mut accounts = vec![AccountMeta::new_readonly(owner.key(), true)];
+    accounts.extend(burn_accounts.iter().map(|account| AccountMeta {
+        pubkey: account.key(),
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+    let burn_instruction = Instruction {
+        program_id: token_messenger.key(),
+        accounts,
+        data,
+    };
+    let mut account_infos = vec![owner.clone()];
+    account_infos.extend_from_slice(burn_accounts);
+    account_infos.push(token_messenger.clone());
+    invoke_signed(&burn_instruction, &account_infos, signer_seeds)?;
+    Ok(())
+}
+
+// Helper function to reject a top-level call. The executor reaches lz_receive through a CPI, so
+// the outer instruction never targets this program; the endpoint clear remains the real check.
+fn require_invoked_by_cpi(instructions: &AccountInfo) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)?;
+    let outer_instruction = load_instruction_at_checked(current_index as usize, instructions)?;
+    require!(outer_instruction.program_id != crate::ID, OmniVaultError::LzReceiveNotInvokedByCpi);
+    Ok(())
+}
+
+// Helper function to clear a received payload with the LayerZero endpoint. VaultStore is the
+// OApp registered with the endpoint and signs for it; the clear accounts (OApp registry,
+// nonce, payload hash, endpoint settings and event authority) come in remaining_accounts.
+fn clear_lz_payload<'info>(
+    endpoint: &AccountInfo<'info>,
+    vault_store: &Account<'info, VaultStore>,
+    clear_accounts: &[AccountInfo<'info>],
+    params: &LzReceiveParams,
+) -> Result<()> {
+    let oapp = vault_store.key();
+    require!(
+        clear_accounts.iter().any(|account| account.key() == oapp),
+        OmniVaultError::NotCalledByEndpoint
+    );
+    
+    let mut data = hash(b"global:clear").to_bytes()[..8].to_vec();
+    LzClearParams {
+        receiver: oapp,
+        src_eid: params.src_eid,
+        sender: params.sender,
+        nonce: params.nonce,
+        guid: params.guid,
+        message: params.message.clone(),
+    }
+    .serialize(&mut data)?;
+    
+    let clear_instruction = Instruction {
+        program_id: endpoint.key(),
+        accounts: clear_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.key() == oapp,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+    let mut account_infos = clear_accounts.to_vec();
+    account_infos.push(endpoint.clone());
+    invoke_signed(
+        &clear_instruction,
+        &account_infos,
+        &[&[b"vault_store".as_ref(), &[vault_store.bump]]],
+    )?;
+    Ok(())
+}
+
+// Helper function to ask the LayerZero endpoint for the native fee of one send from the
+// OApp `sender`. The endpoint answers with a MessagingFee in return data.
+fn quote_lz_fee<'info>(
+    endpoint: &AccountInfo<'info>,
+    sender: Pubkey,
+    chain_send: &ChainSend<'info>,
+    message: &[u8],
+) -> Result<u64> {
+    let endpoint_id = Pubkey::from_str(LAYERZERO_ENDPOINT).unwrap();
+    require!(endpoint.key() == endpoint_id, OmniVaultError::InvalidLayerZeroEndpoint);
+    let mut data = hash(b"global:quote").to_bytes()[..8].to_vec();
+    LzQuoteParams {
+        sender,
+        dst_eid: chain_send.eid,
+        receiver: chain_send.receiver,
+        message: message.to_vec(),
+        options: chain_send.options.clone(),
+        pay_in_lz_token: false,
+    }
+    .serialize(&mut data)?;
+    
+    let quote_instruction = Instruction {
+        program_id: endpoint_id,
+        accounts: chain_send
+            .quote_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: false,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+    let mut account_infos = chain_send.quote_accounts.to_vec();
+    account_infos.push(endpoint.clone());
+    invoke(&quote_instruction, &account_infos)?;
+    
+    read_quote_fee(get_return_data(), &endpoint_id)
+}
+
+// Helper function to send one message through the LayerZero endpoint with the params it was
+// quoted with. VaultStore is the OApp and signs; the payer among the send accounts pays
+// `native_fee`.
+fn send_lz_message<'info>(
+    endpoint: &AccountInfo<'info>,
+    vault_store: &Account<'info, VaultStore>,
+    chain_send: &ChainSend<'info>,
+    message: &[u8],
+    native_fee: u64,
+) -> Result<()> {
+    let oapp = vault_store.key();
+    require!(
+        chain_send.send_accounts.first().is_some_and(|account| account.key() == oapp),
+        OmniVaultError::MissingChainConfig
+    );
+    let mut data = hash(b"global:send").to_bytes()[..8].to_vec();
+    LzSendParams {
+        dst_eid: chain_send.eid,
+        receiver: chain_send.receiver,
+        message: message.to_vec(),
+        options: chain_send.options.clone(),
+        native_fee,
+        lz_token_fee: 0,
+    }
+    .serialize(&mut data)?;
+    
+    let send_instruction = Instruction {
+        program_id: endpoint.key(),
+        accounts: chain_send
+            .send_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.key() == oapp || account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+    let mut account_infos = chain_send.send_accounts.to_vec();
+    account_infos.push(endpoint.clone());
+    invoke_signed(
+        &send_instruction,
+        &account_infos,
+        &[&[b"vault_store".as_ref(), &[vault_store.bump]]],
+    )?;
+    Ok(())
+}
+
+// Helper function to read the native fee out of the endpoint's MessagingFee return data
+fn read_quote_fee(return_data: Option<(Pubkey, Vec<u8>)>, endpoint_id: &Pubkey) -> Result<u64> {
+    let (program_id, fee_data) = return_data.ok_or(OmniVaultError::InvalidFeeQuote)?;
+    require!(program_id == *endpoint_id, OmniVaultError::InvalidFeeQuote);
+    let fee = MessagingFee::try_from_slice(&fee_data).map_err(|_| OmniVaultError::InvalidFeeQuote)?;
+    Ok(fee.native_fee)
+}
+
+// Helper function to measure the lamports a payer spent on sends and hold them to the budget
+fn native_fee_spent(lamports_before: u64, lamports_after: u64, max_native_fee: u64) -> Result<u64> {
+    let spent = lamports_before.saturating_sub(lamports_after);
+    require!(spent <= max_native_fee, OmniVaultError::MessagingFeeTooHigh);
+    Ok(spent)
+}
+
+// Helper function to append one executor option as [worker id][u16 size][option type][params],
+// where size counts the option type byte plus the params
+fn push_executor_option(options: &mut Vec<u8>, option_type: u8, params: &[u8]) {
+    options.push(LZ_EXECUTOR_WORKER_ID);
+    options.extend_from_slice(&(params.len() as u16 + 1).to_be_bytes());
+    options.push(option_type);
+    options.extend_from_slice(params);
+}
+
+// Helper function to find the best chain based on risk profile
+fn find_best_chain<'a>(chain_yields: &'a [ChainYield], risk_profile: &RiskProfile) -> Option<&'a ChainYield> {
+    if chain_yields.is_empty() {
+        return None;
+    }
+    
+    match risk_profile {
+        RiskProfile::Conservative => {
+            // Prioritize low risk, then yield
+            chain_yields
+                .iter()
+                .filter(|cy| cy.risk_score <= 30)
+                .max_by_key(|cy| cy.apy)
+        }
+        RiskProfile::Moderate => {
+            // Balance risk and yield
+            chain_yields
+                .iter()
+                .max_by_key(|cy| cy.apy.saturating_sub(cy.risk_score * 2))
+        }
+        RiskProfile::Aggressive => {
+            // Prioritize highest yield
+            chain_yields.iter().max_by_key(|cy| cy.apy)
+        }
+    }
+}
+
+// Account structures
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VaultStore::INIT_SPACE,
+        seeds = [b"vault_store"],
+        bump
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", owner.key().as_ref(), &vault_store.total_vaults.to_le_bytes()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + YieldTracker::INIT_SPACE,
+        seeds = [b"yield_tracker", vault.key().as_ref()],
+        bump
+    )]
+    pub yield_tracker: Account<'info, YieldTracker>,
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"share_mint", vault.key().as_ref()],
+        bump,
+        mint::decimals = asset_mint.decimals,
+        mint::authority = vault,
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"vault_tokens", vault.key().as_ref()],
+        bump,
+        token::mint = asset_mint,
+        token::authority = vault,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+        token::authority = user,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::NativeSol @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositWrappedSol<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.asset_mint == native_mint::ID @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSol<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::NativeSol @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + EmergencyClaim::INIT_SPACE,
+        seeds = [b"emergency_claim", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub emergency_claim: Account<'info, EmergencyClaim>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdrawSol<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::NativeSol @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + EmergencyClaim::INIT_SPACE,
+        seeds = [b"emergency_claim", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub emergency_claim: Account<'info, EmergencyClaim>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemEmergencyClaim<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        mut,
+        seeds = [b"emergency_claim", vault.key().as_ref(), user.key().as_ref()],
+        bump = emergency_claim.bump
+    )]
+    pub emergency_claim: Account<'info, EmergencyClaim>,
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemEmergencyClaimSol<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::NativeSol @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        mut,
+        seeds = [b"emergency_claim", vault.key().as_ref(), user.key().as_ref()],
+        bump = emergency_claim.bump
+    )]
+    pub emergency_claim: Account<'info, EmergencyClaim>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [b"withdrawal_request", vault.key().as_ref(), &vault.withdrawal_queue_tail.to_le_bytes()],
+        bump
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"share_escrow", vault.key().as_ref()],
+        bump,
+        token::mint = share_mint,
+        token::authority = vault,
+    )]
+    pub share_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FulfillWithdrawal<'info> {
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"withdrawal_request", vault.key().as_ref(), &withdrawal_request.index.to_le_bytes()],
+        bump = withdrawal_request.bump
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"share_escrow", vault.key().as_ref()],
+        bump
+    )]
+    pub share_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::Keeper, &keeper.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    pub keeper: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithdrawal<'info> {
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        mut,
+        seeds = [b"withdrawal_request", vault.key().as_ref(), &withdrawal_request.index.to_le_bytes()],
+        bump = withdrawal_request.bump,
+        has_one = user
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"withdrawal_request", vault.key().as_ref(), &withdrawal_request.index.to_le_bytes()],
+        bump = withdrawal_request.bump,
+        has_one = user
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        constraint = user_position.version == USER_POSITION_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"share_escrow", vault.key().as_ref()],
+        bump
+    )]
+    pub share_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestYield<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    pub harvester: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+        token::authority = harvester,
+    )]
+    pub harvester_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestYieldSol<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::NativeSol @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub harvester: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteCrossChainQuery<'info> {
+    #[account(
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        seeds = [b"yield_tracker", vault.key().as_ref()],
+        bump = yield_tracker.bump,
+        constraint = yield_tracker.version == YIELD_TRACKER_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub yield_tracker: Account<'info, YieldTracker>,
+    /// The OApp quotes are requested for
+    #[account(
+        seeds = [b"vault_store"],
+        bump = vault_store.bump
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    /// CHECK: LayerZero Endpoint Program - its return data is only trusted from the known program ID
+    pub endpoint: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueryCrossChainYields<'info> {
+    #[account(
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"yield_tracker", vault.key().as_ref()],
+        bump = yield_tracker.bump,
+        constraint = yield_tracker.version == YIELD_TRACKER_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub yield_tracker: Account<'info, YieldTracker>,
+    /// The OApp registered with the endpoint, signs the send CPIs
+    #[account(
+        seeds = [b"vault_store"],
+        bump = vault_store.bump,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::Keeper, &payer.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    /// CHECK: LayerZero Endpoint Program - verified against known program ID
+    pub endpoint: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: LzReceiveParams)]
+pub struct LzReceive<'info> {
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"yield_tracker", vault.key().as_ref()],
+        bump = yield_tracker.bump,
+        constraint = yield_tracker.version == YIELD_TRACKER_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub yield_tracker: Account<'info, YieldTracker>,
+    #[account(
+        seeds = [b"peer", (params.src_eid as u16).to_le_bytes().as_ref()],
+        bump = peer.bump,
+        constraint = peer.address == params.sender @ OmniVaultError::UnauthorizedPeer
+    )]
+    pub peer: Account<'info, Peer>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + InboundNonce::INIT_SPACE,
+        seeds = [b"inbound_nonce", vault.key().as_ref(), (params.src_eid as u16).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub inbound_nonce: Account<'info, InboundNonce>,
+    /// The OApp registered with the endpoint, signs the clear CPI
+    #[account(
+        seeds = [b"vault_store"],
+        bump = vault_store.bump
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    /// CHECK: LayerZero Endpoint Program - verified against known program ID
+    #[account(address = Pubkey::from_str(LAYERZERO_ENDPOINT).unwrap() @ OmniVaultError::InvalidLayerZeroEndpoint)]
+    pub endpoint: AccountInfo<'info>,
+    /// CHECK: Instructions sysvar, used to reject lz_receive as a top-level instruction
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LzReceiveTypes<'info> {
+    #[account(
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        seeds = [b"yield_tracker", vault.key().as_ref()],
+        bump = yield_tracker.bump,
+        constraint = yield_tracker.version == YIELD_TRACKER_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub yield_tracker: Account<'info, YieldTracker>,
+    /// CHECK: LayerZero Endpoint Program - verified against known program ID
+    pub endpoint: AccountInfo<'info>,
+    /// CHECK: OApp Configuration account - managed by LayerZero
+    pub oapp_config: AccountInfo<'info>,
+    pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RebalanceVault<'info> {
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::Keeper, &authority.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetVaultActive<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(src_chain_id: u16)]
+pub struct SetInboundNonceOrdering<'info> {
+    #[account(
+        has_one = owner,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + InboundNonce::INIT_SPACE,
+        seeds = [b"inbound_nonce", vault.key().as_ref(), src_chain_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub inbound_nonce: Account<'info, InboundNonce>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u16)]
+pub struct SetChainConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ChainConfig::INIT_SPACE,
+        seeds = [b"chain_config", chain_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub chain_config: Account<'info, ChainConfig>,
+    #[account(
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u16)]
+pub struct SetPeer<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Peer::INIT_SPACE,
+        seeds = [b"peer", chain_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub peer: Account<'info, Peer>,
+    #[account(
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVaultStore<'info> {
+    /// CHECK: Deserialized by the handler after it is resized to the current layout
+    #[account(
+        mut,
+        seeds = [b"vault_store"],
+        bump
+    )]
+    pub vault_store: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    /// CHECK: Owned by this program; the handler checks the legacy Vault layout and its seeds
+    #[account(mut, owner = crate::ID)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: Owned by this program; the handler checks it belongs to the vault after resizing
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"yield_tracker", vault.key().as_ref()],
+        bump
+    )]
+    pub yield_tracker: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"share_mint", vault.key().as_ref()],
+        bump,
+        mint::decimals = asset_mint.decimals,
+        mint::authority = vault,
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    /// Holds the shares minted for legacy deposits until each position is migrated
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"legacy_shares", vault.key().as_ref()],
+        bump,
+        token::mint = share_mint,
+        token::authority = vault,
+    )]
+    pub legacy_share_escrow: InterfaceAccount<'info, TokenAccount>,
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"vault_tokens", vault.key().as_ref()],
+        bump,
+        token::mint = asset_mint,
+        token::authority = vault,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// Token account the legacy vault took deposits into, swept into vault_token_account
+    #[account(
+        mut,
+        token::mint = asset_mint,
+        token::authority = vault,
+    )]
+    pub legacy_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserPosition<'info> {
+    /// CHECK: Owned by this program; the handler checks the UserPosition discriminator after resizing
+    #[account(mut, owner = crate::ID)]
+    pub user_position: UncheckedAccount<'info>,
+    #[account(
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"legacy_shares", vault.key().as_ref()],
+        bump
+    )]
+    pub legacy_share_escrow: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Owner of the position, checked against it by the handler
+    pub user: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = share_mint,
+        associated_token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        has_one = user,
+        constraint = user_position.version == USER_POSITION_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    /// The user's canonical share account, the one deposits mint into
+    #[account(
+        associated_token::mint = vault.share_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAssetPosition<'info> {
+    #[account(
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        seeds = [b"vault_asset", vault.key().as_ref(), vault_asset.mint.as_ref()],
+        bump = vault_asset.bump
+    )]
+    pub vault_asset: Account<'info, VaultAsset>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"asset_position", vault_asset.key().as_ref(), user.key().as_ref()],
+        bump = asset_position.bump,
+        has_one = user
+    )]
+    pub asset_position: Account<'info, AssetPosition>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveVaultAsset<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"vault_asset", vault.key().as_ref(), vault_asset.mint.as_ref()],
+        bump = vault_asset.bump
+    )]
+    pub vault_asset: Account<'info, VaultAsset>,
+    #[account(mut, address = vault_asset.token_account)]
+    pub asset_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault_asset.mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    /// Receives any residual balance; only needed when the asset account is not empty
+    #[account(
+        mut,
+        token::mint = vault_asset.mint,
+    )]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"yield_tracker", vault.key().as_ref()],
+        bump = yield_tracker.bump,
+        constraint = yield_tracker.version == YIELD_TRACKER_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub yield_tracker: Account<'info, YieldTracker>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"share_escrow", vault.key().as_ref()],
+        bump,
+    )]
+    pub share_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"legacy_shares", vault.key().as_ref()],
+        bump,
+    )]
+    pub legacy_share_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: The vault's PendingConfig address, closed by the handler when a change is queued
+    #[account(
+        mut,
+        seeds = [b"pending_config", vault.key().as_ref()],
+        bump,
+    )]
+    pub pending_config: UncheckedAccount<'info>,
+    /// Receives any residual balance; only needed when the vault token account is not empty
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+    )]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct QueueVaultConfig<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PendingConfig::INIT_SPACE,
+        seeds = [b"pending_config", vault.key().as_ref()],
+        bump
+    )]
+    pub pending_config: Account<'info, PendingConfig>,
+    #[account(
+        has_one = owner,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteVaultConfig<'info> {
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_config", vault.key().as_ref()],
+        bump = pending_config.bump,
+        has_one = proposer
+    )]
+    pub pending_config: Account<'info, PendingConfig>,
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    /// CHECK: Receives the pending config rent; bound by has_one
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueueStoreConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PendingConfig::INIT_SPACE,
+        seeds = [b"pending_config", vault_store.key().as_ref()],
+        bump
+    )]
+    pub pending_config: Account<'info, PendingConfig>,
+    #[account(
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteStoreConfig<'info> {
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_config", vault_store.key().as_ref()],
+        bump = pending_config.bump,
+        has_one = proposer
+    )]
+    pub pending_config: Account<'info, PendingConfig>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    /// CHECK: Receives the pending config rent; bound by has_one
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConfig<'info> {
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_config", pending_config.target.as_ref()],
+        bump = pending_config.bump,
+        has_one = proposer
+    )]
+    pub pending_config: Account<'info, PendingConfig>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::Guardian, &canceller.key())
+            || vault_store.authority == canceller.key()
+            || pending_config.proposer == canceller.key() @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    /// CHECK: Receives the pending config rent; bound by has_one
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub canceller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        constraint = vault_store.pending_authority == pending_authority.key() @ OmniVaultError::UnauthorizedCaller,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    pub pending_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeVaultOwner<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptVaultOwner<'info> {
+    #[account(
+        mut,
+        constraint = vault.pending_owner == pending_owner.key() @ OmniVaultError::UnauthorizedCaller,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    pub pending_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRoles<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Roles::INIT_SPACE,
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump
+    )]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRoles<'info> {
+    #[account(
+        mut,
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyPause<'info> {
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = vault_store.authority == guardian.key()
+            || roles.has_role(Role::Guardian, &guardian.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResumeOperations<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetStorePauseFlags<'info> {
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = vault_store.authority == guardian.key()
+            || roles.has_role(Role::Guardian, &guardian.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClearStorePauseFlags<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetVaultPauseFlags<'info> {
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = vault_store.authority == guardian.key()
+            || roles.has_role(Role::Guardian, &guardian.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    pub guardian: Signer<'info>,
 }
 
-// Account structures
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct ClearVaultPauseFlags<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + VaultStore::INIT_SPACE,
-        seeds = [b"vault_store"],
-        bump
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
     )]
     pub vault_store: Account<'info, VaultStore>,
-    #[account(mut)]
     pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateVault<'info> {
+#[instruction(create_key: Pubkey)]
+pub struct CreateMultisig<'info> {
     #[account(
         init,
-        payer = owner,
-        space = 8 + Vault::INIT_SPACE,
-        seeds = [b"vault", owner.key().as_ref(), &vault_store.total_vaults.to_le_bytes()],
+        payer = payer,
+        space = 8 + Multisig::INIT_SPACE,
+        seeds = [b"multisig", create_key.as_ref()],
         bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub multisig: Account<'info, Multisig>,
+    /// CHECK: PDA that signs for the multisig; holds no data
     #[account(
-        init,
-        payer = owner,
-        space = 8 + YieldTracker::INIT_SPACE,
-        seeds = [b"yield_tracker", vault.key().as_ref()],
+        seeds = [b"multisig_signer", multisig.key().as_ref()],
         bump
     )]
-    pub yield_tracker: Account<'info, YieldTracker>,
+    pub multisig_signer: UncheckedAccount<'info>,
     #[account(mut)]
-    pub vault_store: Account<'info, VaultStore>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Deposit<'info> {
-    #[account(mut)]
-    pub vault: Account<'info, Vault>,
+pub struct CreateMultisigProposal<'info> {
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + UserPosition::INIT_SPACE,
-        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        mut,
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + MultisigProposal::INIT_SPACE,
+        seeds = [b"multisig_proposal", multisig.key().as_ref(), &multisig.proposal_count.to_le_bytes()],
         bump
     )]
-    pub user_position: Account<'info, UserPosition>,
-    #[account()]
-    pub vault_store: Account<'info, VaultStore>,
-    #[account(mut)]
-    pub user: Signer<'info>,
+    pub proposal: Account<'info, MultisigProposal>,
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub proposer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct DepositSol<'info> {
-    #[account(mut)]
-    pub vault: Account<'info, Vault>,
+pub struct ApproveMultisigProposal<'info> {
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + UserPosition::INIT_SPACE,
-        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        mut,
+        has_one = multisig
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMultisigProposal<'info> {
+    #[account(
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    /// CHECK: PDA that signs for the multisig; holds no data
+    #[account(
+        seeds = [b"multisig_signer", multisig.key().as_ref()],
+        bump = multisig.signer_bump
+    )]
+    pub multisig_signer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        has_one = multisig
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ChangeMultisig<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        seeds = [b"multisig_signer", multisig.key().as_ref()],
+        bump = multisig.signer_bump
+    )]
+    pub multisig_signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeTreasury<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FeeTreasury::INIT_SPACE,
+        seeds = [b"fee_treasury", mint.key().as_ref()],
         bump
     )]
-    pub user_position: Account<'info, UserPosition>,
-    #[account()]
+    pub fee_treasury: Account<'info, FeeTreasury>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"treasury_tokens", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = fee_treasury,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        has_one = authority,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault_store: Account<'info, VaultStore>,
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Withdraw<'info> {
-    #[account(mut)]
+pub struct CollectFees<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
-    #[account(mut)]
-    pub user_position: Account<'info, UserPosition>,
-    #[account()]
+    #[account(
+        mut,
+        seeds = [b"fee_treasury", vault_token_account.mint.as_ref()],
+        bump = fee_treasury.bump
+    )]
+    pub fee_treasury: Account<'info, FeeTreasury>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = fee_treasury.token_account
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault_store: Account<'info, VaultStore>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = vault_store.authority == fee_manager.key()
+            || roles.has_role(Role::FeeManager, &fee_manager.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    pub fee_manager: Signer<'info>,
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawSol<'info> {
-    #[account(mut)]
+pub struct CollectSolFees<'info> {
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::NativeSol @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
-    #[account(mut)]
-    pub user_position: Account<'info, UserPosition>,
-    #[account()]
+    #[account(
+        mut,
+        seeds = [b"fee_treasury", native_mint::ID.as_ref()],
+        bump = fee_treasury.bump
+    )]
+    pub fee_treasury: Account<'info, FeeTreasury>,
+    #[account(
+        mut,
+        address = fee_treasury.token_account
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault_store: Account<'info, VaultStore>,
-    #[account(mut)]
-    pub user: Signer<'info>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = vault_store.authority == fee_manager.key()
+            || roles.has_role(Role::FeeManager, &fee_manager.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    pub fee_manager: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct QueryCrossChainYields<'info> {
-    #[account()]
-    pub vault: Account<'info, Vault>,
-    #[account(mut)]
-    pub yield_tracker: Account<'info, YieldTracker>,
-    /// CHECK: LayerZero Endpoint Program - verified against known program ID
-    pub endpoint: AccountInfo<'info>,
-    /// CHECK: OApp Configuration account - managed by LayerZero
-    pub oapp_config: AccountInfo<'info>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        seeds = [b"fee_treasury", mint.key().as_ref()],
+        bump = fee_treasury.bump
+    )]
+    pub fee_treasury: Account<'info, FeeTreasury>,
+    #[account(
+        mut,
+        address = fee_treasury.token_account
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = vault_store.authority == fee_manager.key()
+            || roles.has_role(Role::FeeManager, &fee_manager.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
+    pub fee_manager: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct LzReceive<'info> {
-    #[account(mut)]
+pub struct AddVaultAsset<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + VaultAsset::INIT_SPACE,
+        seeds = [b"vault_asset", vault.key().as_ref(), asset_mint.key().as_ref()],
+        bump
+    )]
+    pub vault_asset: Account<'info, VaultAsset>,
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"asset_tokens", vault_asset.key().as_ref()],
+        bump,
+        token::mint = asset_mint,
+        token::authority = vault,
+    )]
+    pub asset_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
     #[account(mut)]
-    pub yield_tracker: Account<'info, YieldTracker>,
-    /// CHECK: LayerZero Endpoint Program - verified against known program ID
-    pub endpoint: AccountInfo<'info>,
-    /// CHECK: OApp Configuration account - managed by LayerZero
-    pub oapp_config: AccountInfo<'info>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct LzReceiveTypes<'info> {
+pub struct DepositAsset<'info> {
+    #[account(
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
-    pub yield_tracker: Account<'info, YieldTracker>,
-    /// CHECK: LayerZero Endpoint Program - verified against known program ID
-    pub endpoint: AccountInfo<'info>,
-    /// CHECK: OApp Configuration account - managed by LayerZero
-    pub oapp_config: AccountInfo<'info>,
-    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault_asset", vault.key().as_ref(), vault_asset.mint.as_ref()],
+        bump = vault_asset.bump
+    )]
+    pub vault_asset: Account<'info, VaultAsset>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + AssetPosition::INIT_SPACE,
+        seeds = [b"asset_position", vault_asset.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub asset_position: Account<'info, AssetPosition>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault_asset.mint,
+        token::authority = user,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault_asset.token_account)]
+    pub asset_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault_asset.mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RebalanceVault<'info> {
-    #[account(mut)]
+pub struct WithdrawAsset<'info> {
+    #[account(
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
-    #[account()]
+    #[account(
+        mut,
+        seeds = [b"vault_asset", vault.key().as_ref(), vault_asset.mint.as_ref()],
+        bump = vault_asset.bump
+    )]
+    pub vault_asset: Account<'info, VaultAsset>,
+    #[account(
+        mut,
+        seeds = [b"asset_position", vault_asset.key().as_ref(), user.key().as_ref()],
+        bump = asset_position.bump
+    )]
+    pub asset_position: Account<'info, AssetPosition>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault_store: Account<'info, VaultStore>,
-    pub authority: Signer<'info>,
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault_asset.mint,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault_asset.token_account)]
+    pub asset_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault_asset.mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateVaultConfig<'info> {
+pub struct HarvestAssetYield<'info> {
     #[account(
-        mut,
-        has_one = owner
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
     )]
     pub vault: Account<'info, Vault>,
-    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault_asset", vault.key().as_ref(), vault_asset.mint.as_ref()],
+        bump = vault_asset.bump
+    )]
+    pub vault_asset: Account<'info, VaultAsset>,
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
+    pub harvester: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = vault_asset.mint,
+        token::authority = harvester,
+    )]
+    pub harvester_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault_asset.token_account)]
+    pub asset_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault_asset.mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct EmergencyPause<'info> {
+pub struct CollectAssetFees<'info> {
+    #[account(
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"vault_asset", vault.key().as_ref(), vault_asset.mint.as_ref()],
+        bump = vault_asset.bump
+    )]
+    pub vault_asset: Account<'info, VaultAsset>,
+    #[account(
+        mut,
+        seeds = [b"fee_treasury", vault_asset.mint.as_ref()],
+        bump = fee_treasury.bump
+    )]
+    pub fee_treasury: Account<'info, FeeTreasury>,
+    #[account(mut, address = vault_asset.token_account)]
+    pub asset_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = fee_treasury.token_account
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
     #[account(
-        mut,
-        has_one = authority
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
     )]
     pub vault_store: Account<'info, VaultStore>,
-    pub authority: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct ResumeOperations<'info> {
     #[account(
-        mut,
-        has_one = authority
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = vault_store.authority == fee_manager.key()
+            || roles.has_role(Role::FeeManager, &fee_manager.key()) @ OmniVaultError::UnauthorizedCaller
     )]
-    pub vault_store: Account<'info, VaultStore>,
-    pub authority: Signer<'info>,
+    pub roles: Account<'info, Roles>,
+    pub fee_manager: Signer<'info>,
+    #[account(address = vault_asset.mint)]
+    pub asset_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 // CCTP Context Structs
 
 #[derive(Accounts)]
 pub struct DepositUSDCViaCCTP<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
     #[account(
         init_if_needed,
         payer = user,
         space = 8 + UserPosition::INIT_SPACE,
         seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
-        bump
+        bump,
+        constraint = user_position.version == USER_POSITION_VERSION
+            || user_position.user == Pubkey::default() @ OmniVaultError::AccountVersionMismatch
     )]
     pub user_position: Account<'info, UserPosition>,
-    #[account()]
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault_store: Account<'info, VaultStore>,
     #[account()]
     pub cctp_config: Account<'info, CCTPConfig>,
+    /// CHECK: CCTP MessageTransmitter program configured for this deployment
+    #[account(executable, address = cctp_config.message_transmitter)]
+    pub message_transmitter: AccountInfo<'info>,
     #[account(mut)]
     pub user: Signer<'info>,
-    /// CHECK: USDC Mint account
+    /// CHECK: USDC Mint account; must be the vault's asset
+    #[account(address = vault.asset_mint)]
     pub usdc_mint: AccountInfo<'info>,
-    #[account(mut)]
-    pub user_usdc_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub vault_usdc_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+    )]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct WithdrawUSDCViaCCTP<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = vault.asset_kind == AssetKind::Spl @ OmniVaultError::AssetKindMismatch,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        constraint = user_position.version == USER_POSITION_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub user_position: Account<'info, UserPosition>,
-    #[account()]
+    #[account(
+        mut,
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault_store: Account<'info, VaultStore>,
     #[account()]
     pub cctp_config: Account<'info, CCTPConfig>,
     #[account(mut)]
     pub user: Signer<'info>,
-    /// CHECK: USDC Mint account
+    /// CHECK: USDC Mint account; must be the vault's asset
+    #[account(address = vault.asset_mint)]
     pub usdc_mint: AccountInfo<'info>,
-    #[account(mut)]
-    pub user_usdc_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub vault_usdc_account: Account<'info, TokenAccount>,
-    /// CHECK: CCTP TokenMessenger program
+    #[account(
+        mut,
+        token::mint = vault.asset_mint,
+    )]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.token_account)]
+    pub vault_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: CCTP TokenMessengerMinter program configured for this deployment
+    #[account(executable, address = cctp_config.token_messenger)]
     pub token_messenger: AccountInfo<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
 pub struct RebalanceWithCCTP<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"yield_tracker", vault.key().as_ref()],
+        bump = yield_tracker.bump,
+        constraint = yield_tracker.version == YIELD_TRACKER_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub yield_tracker: Account<'info, YieldTracker>,
-    #[account()]
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault_store: Account<'info, VaultStore>,
     #[account()]
     pub cctp_config: Account<'info, CCTPConfig>,
+    #[account(
+        seeds = [b"roles", vault_store.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::Keeper, &authority.key()) @ OmniVaultError::UnauthorizedCaller
+    )]
+    pub roles: Account<'info, Roles>,
     pub authority: Signer<'info>,
     /// CHECK: CCTP TokenMessenger program
     pub token_messenger: AccountInfo<'info>,
@@ -1147,11 +6024,16 @@ pub struct RebalanceWithCCTP<'info> {
 
 #[derive(Accounts)]
 pub struct HandleCCTPHook<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = vault.version == VAULT_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault: Account<'info, Vault>,
     #[account()]
     pub hook_registry: Account<'info, HookRegistry>,
-    #[account()]
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
     pub vault_store: Account<'info, VaultStore>,
     /// CHECK: CCTP Message Transmitter
     pub message_transmitter: AccountInfo<'info>,
@@ -1162,6 +6044,10 @@ pub struct HandleCCTPHook<'info> {
 pub struct ProcessCCTPAttestation<'info> {
     #[account(mut)]
     pub cctp_transfer_tracker: Account<'info, CCTPTransferTracker>,
+    #[account(
+        constraint = vault_store.version == VAULT_STORE_VERSION @ OmniVaultError::AccountVersionMismatch
+    )]
+    pub vault_store: Account<'info, VaultStore>,
     /// CHECK: CCTP Message Transmitter
     pub message_transmitter: AccountInfo<'info>,
     pub payer: Signer<'info>,
@@ -1173,13 +6059,19 @@ pub struct ProcessCCTPAttestation<'info> {
 pub struct VaultStore {
     pub authority: Pubkey,
     pub total_vaults: u64,
-    pub total_tvl: u64,
+    pub legacy_total_tvl: u64, // Always 0, kept for the legacy layout: TVL is tracked per mint in mint_tvls
     pub fee_rate: u16, // Basis points (100 = 1%)
     pub bump: u8,
     pub last_global_rebalance: i64,
     pub emergency_pause: bool,
     #[max_len(10)]
     pub supported_chains: Vec<u16>,
+    pub pending_authority: Pubkey, // Default key when no transfer is pending
+    pub config_delay: i64, // Timelock in seconds for queued configuration changes
+    #[max_len(16)]
+    pub mint_tvls: Vec<MintTvl>, // TVL and global deposit cap per asset mint, in its base units
+    pub pause_flags: u8, // pause_flags bits paused for every vault
+    pub version: u8, // Schema version, see VAULT_STORE_VERSION
 }
 
 #[account]
@@ -1200,6 +6092,228 @@ pub struct Vault {
     pub rebalance_threshold: u64,
     pub emergency_exit: bool,
     pub bump: u8,
+    pub share_mint: Pubkey,
+    pub share_price: u64, // Assets per share, scaled by PRICE_PRECISION
+    pub accrued_fees: u64, // Fees held by the vault awaiting collect_fees, excluded from NAV
+    pub management_fee_bps: u16, // Annual management fee in basis points
+    pub performance_fee_bps: u16, // Basis points of yield above the high-water mark
+    pub high_water_mark: u64, // Highest share price fees have been charged at
+    pub last_fee_accrual: i64,
+    pub creator: Pubkey, // Original owner; vault PDA seeds stay fixed across ownership changes
+    pub pending_owner: Pubkey, // Default key when no transfer is pending
+    pub deposit_cap: u64, // Maximum NAV accepted by deposits, 0 = uncapped
+    pub asset_mint: Pubkey,
+    pub token_account: Pubkey, // Vault-owned PDA holding the asset
+    #[max_len(8)]
+    pub extra_assets: Vec<Pubkey>, // Additional mints, each tracked by a VaultAsset
+    pub asset_kind: AssetKind, // Denomination of total_deposits and total_yield
+    pub withdrawal_queue_head: u64, // Index of the next WithdrawalRequest to fulfill
+    pub withdrawal_queue_tail: u64, // Index assigned to the next WithdrawalRequest
+    pub reserved_withdrawals: u64, // Fulfilled amounts held in token_account awaiting claim
+    pub lock_period: i64, // Seconds after a deposit during which exits are locked or penalized
+    pub early_exit_penalty_bps: u16, // Penalty at the start of the lock, 0 = hard lock
+    pub emergency_claims: u64, // Outstanding EmergencyClaim amounts, excluded from NAV
+    pub emergency_claim_units: u128, // Units issued to outstanding EmergencyClaims
+    pub emergency_claim_index: u128, // Recovered cash per claim unit, see EMERGENCY_INDEX_PRECISION
+    pub emergency_claim_epoch: u32, // Bumped whenever all claims have been repaid
+    pub emergency_reserved: u64, // Recovered liquidity set aside for claims to redeem
+    pub emergency_synced_liquidity: u64, // Holders' free liquidity as of the last emergency sync
+    pub pause_flags: u8, // pause_flags bits paused for this vault only
+    pub version: u8, // Schema version, see VAULT_VERSION
+}
+
+impl Vault {
+    /// Net asset value backing the share supply: principal plus realized yield
+    pub fn total_assets(&self) -> u64 {
+        self.total_deposits.saturating_add(self.total_yield)
+    }
+
+    /// Debit a redemption from principal and yield in proportion to their weight in NAV
+    pub fn debit_assets(&mut self, amount: u64) -> Result<()> {
+        let total_assets = self.total_assets();
+        require!(amount <= total_assets, OmniVaultError::InsufficientBalance);
+        if amount == 0 {
+            return Ok(());
+        }
+        let from_yield = (amount as u128 * self.total_yield as u128 / total_assets as u128) as u64;
+        self.total_yield -= from_yield;
+        self.total_deposits -= amount - from_yield;
+        Ok(())
+    }
+
+    /// Debit a redemption of `amount`, of which `penalty` is forfeited to remaining holders
+    pub fn debit_exit(&mut self, amount: u64, penalty: u64) -> Result<()> {
+        self.debit_assets(amount)?;
+        self.total_yield += penalty;
+        Ok(())
+    }
+
+    /// Refresh the stored exchange rate for the given share supply
+    pub fn update_share_price(&mut self, total_shares: u64) {
+        self.share_price = share_price(self.total_assets(), total_shares);
+    }
+
+    /// Switch to emergency mode. The first emergency action records the liquidity on hand as
+    /// the holders' baseline.
+    pub fn enter_emergency(&mut self) {
+        if !self.emergency_exit {
+            self.emergency_exit = true;
+            self.emergency_synced_liquidity = u64::MAX;
+        }
+    }
+
+    /// Split liquidity recovered since the last sync between outstanding emergency claims and
+    /// remaining holders, pro rata to claims and NAV. The claims' part is set aside and credited
+    /// to the claim index.
+    pub fn sync_emergency_liquidity(&mut self, mut liquidity: u64) -> Result<()> {
+        if liquidity > self.emergency_synced_liquidity && self.emergency_claims > 0 {
+            let recovered = (liquidity - self.emergency_synced_liquidity) as u128;
+            let claims = self.emergency_claims as u128;
+            let total = self.total_assets() as u128 + claims;
+            let share = (recovered * claims / total).min(claims) as u64;
+            self.emergency_claim_index = self
+                .emergency_claim_index
+                .checked_add(share as u128 * EMERGENCY_INDEX_PRECISION / self.emergency_claim_units)
+                .ok_or(OmniVaultError::MathOverflow)?;
+            self.emergency_reserved += share;
+            self.emergency_claims -= share;
+            liquidity -= share;
+            self.retire_repaid_claims();
+        }
+        self.emergency_synced_liquidity = liquidity;
+        Ok(())
+    }
+
+    /// Size an emergency exit from the holders' synced liquidity at the current NAV, and take
+    /// it out of that liquidity
+    pub fn emergency_exit_payout(&mut self, entitlement: u64) -> Result<u64> {
+        let total_assets = self.total_assets();
+        if total_assets == 0 {
+            return Ok(0);
+        }
+        let paid = u64::try_from(
+            entitlement as u128 * self.emergency_synced_liquidity as u128 / total_assets as u128,
+        )
+        .map_err(|_| OmniVaultError::MathOverflow)?
+        .min(entitlement);
+        self.emergency_synced_liquidity -= paid;
+        Ok(paid)
+    }
+
+    /// Add an unpaid emergency remainder to the outstanding claims and return the units it is
+    /// worth at the current claims-to-units rate
+    pub fn record_emergency_claim(&mut self, amount: u64) -> Result<u128> {
+        let units = if self.emergency_claims == 0 {
+            amount as u128
+        } else {
+            (amount as u128)
+                .checked_mul(self.emergency_claim_units)
+                .ok_or(OmniVaultError::MathOverflow)?
+                / self.emergency_claims as u128
+        };
+        self.emergency_claims += amount;
+        self.emergency_claim_units = self
+            .emergency_claim_units
+            .checked_add(units)
+            .ok_or(OmniVaultError::MathOverflow)?;
+        Ok(units)
+    }
+
+    /// Start a new claim epoch once every outstanding claim has been repaid, so claims settled
+    /// later see themselves as fully recovered
+    pub fn retire_repaid_claims(&mut self) {
+        if self.emergency_claims == 0 && self.emergency_claim_units > 0 {
+            self.emergency_claim_epoch += 1;
+            self.emergency_claim_units = 0;
+        }
+    }
+}
+
+/// Vault layout before versioning, read by migrate_vault. The original update_vault_config
+/// rewrote target_chains in place, so a shrunk list leaves stale bytes after `bump` and the
+/// current layout cannot be read over it.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct VaultV0 {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub risk_profile: RiskProfile,
+    pub total_deposits: u64,
+    pub total_yield: u64,
+    pub min_deposit: u64,
+    pub is_active: bool,
+    pub last_rebalance: i64,
+    #[max_len(10)]
+    pub target_chains: Vec<u16>,
+    pub current_best_chain: u16,
+    pub current_apy: u64,
+    pub rebalance_threshold: u64,
+    pub emergency_exit: bool,
+    pub bump: u8,
+}
+
+impl VaultV0 {
+    /// Read a legacy vault from its account data, ignoring whatever follows `bump`
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= 8 && data[..8] == *Vault::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        VaultV0::deserialize(&mut &data[8..]).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+
+    /// Carry the legacy fields over into the current layout
+    pub fn into_vault(
+        self,
+        share_mint: Pubkey,
+        asset_mint: Pubkey,
+        token_account: Pubkey,
+        asset_kind: AssetKind,
+        now: i64,
+    ) -> Vault {
+        Vault {
+            id: self.id,
+            owner: self.owner,
+            risk_profile: self.risk_profile,
+            total_deposits: self.total_deposits,
+            total_yield: self.total_yield,
+            min_deposit: self.min_deposit,
+            is_active: self.is_active,
+            last_rebalance: self.last_rebalance,
+            target_chains: self.target_chains,
+            current_best_chain: self.current_best_chain,
+            current_apy: self.current_apy,
+            rebalance_threshold: self.rebalance_threshold,
+            emergency_exit: self.emergency_exit,
+            bump: self.bump,
+            share_mint,
+            share_price: PRICE_PRECISION,
+            accrued_fees: 0,
+            management_fee_bps: 0,
+            performance_fee_bps: 0,
+            high_water_mark: PRICE_PRECISION,
+            last_fee_accrual: now,
+            creator: self.owner,
+            pending_owner: Pubkey::default(),
+            deposit_cap: 0,
+            asset_mint,
+            token_account,
+            extra_assets: vec![],
+            asset_kind,
+            withdrawal_queue_head: 0,
+            withdrawal_queue_tail: 0,
+            reserved_withdrawals: 0,
+            lock_period: 0,
+            early_exit_penalty_bps: 0,
+            emergency_claims: 0,
+            emergency_claim_units: 0,
+            emergency_claim_index: 0,
+            emergency_claim_epoch: 0,
+            emergency_reserved: 0,
+            emergency_synced_liquidity: if self.emergency_exit { u64::MAX } else { 0 },
+            pause_flags: 0,
+            version: VAULT_VERSION,
+        }
+    }
 }
 
 #[account]
@@ -1211,16 +6325,347 @@ pub struct UserPosition {
     pub last_deposit: i64,
     pub last_withdrawal: i64,
     pub bump: u8,
+    pub shares: u64, // Shares minted to this position and not yet redeemed or escrowed
+    pub locked_shares: u64, // Part of shares still inside the lock, unlocking together at last_deposit + lock_period
+    pub version: u8, // Schema version, see USER_POSITION_VERSION
+}
+
+impl UserPosition {
+    /// Record shares minted to the position. Shares the holder received by transfer are taken
+    /// in as deposited now, and last_deposit becomes the share-weighted deposit time of
+    /// everything still locked.
+    pub fn record_deposit(&mut self, vault: &Vault, balance_before: u64, shares: u64, now: i64) {
+        let received = balance_before.saturating_sub(self.shares);
+        let locked = if self.unlocked(vault, now) { 0 } else { self.locked_shares };
+        let added = shares + received;
+        self.last_deposit = ((self.last_deposit as i128 * locked as i128 + now as i128 * added as i128)
+            / (locked as i128 + added as i128)) as i64;
+        self.locked_shares = locked + added;
+        self.shares += added;
+    }
+
+    /// Price the early-exit penalty on redeeming `shares` worth `amount` from a share account
+    /// holding `balance`, and release the shares from the position. Shares the position did
+    /// not deposit itself carry no deposit time and pay the full penalty.
+    pub fn redeem_shares(&mut self, vault: &Vault, balance: u64, shares: u64, amount: u64, now: i64) -> Result<u64> {
+        let (locked, received) = self.locked_split(vault, balance, shares, now);
+        let mut penalty = 0;
+        if locked > 0 {
+            let locked_amount = (amount as u128 * locked as u128 / shares as u128) as u64;
+            penalty += early_exit_penalty(vault, self.last_deposit, locked_amount, now)?;
+        }
+        if received > 0 {
+            let received_amount = (amount as u128 * received as u128 / shares as u128) as u64;
+            penalty += early_exit_penalty(vault, now, received_amount, now)?;
+        }
+        self.release_shares(vault, balance, shares, now);
+        Ok(penalty)
+    }
+
+    /// Take redeemed or escrowed shares out of the position, unlocked ones first
+    pub fn release_shares(&mut self, vault: &Vault, balance: u64, shares: u64, now: i64) {
+        let (locked, received) = self.locked_split(vault, balance, shares, now);
+        self.shares = self.shares.saturating_sub(shares - received);
+        self.locked_shares = if self.unlocked(vault, now) {
+            0
+        } else {
+            self.locked_shares.saturating_sub(locked)
+        };
+    }
+
+    /// Split `shares` redeemed from `balance` into the locked part of the position's own shares
+    /// and shares received from elsewhere. The position's unlocked shares are used first.
+    pub fn locked_split(&self, vault: &Vault, balance: u64, shares: u64, now: i64) -> (u64, u64) {
+        if vault.lock_period == 0 {
+            return (0, 0);
+        }
+        let own = self.shares.min(balance);
+        let locked = if self.unlocked(vault, now) { 0 } else { self.locked_shares.min(own) };
+        let from_unlocked = shares.min(own - locked);
+        let from_locked = (shares - from_unlocked).min(locked);
+        (from_locked, shares - from_unlocked - from_locked)
+    }
+
+    fn unlocked(&self, vault: &Vault, now: i64) -> bool {
+        now >= self.last_deposit.saturating_add(vault.lock_period)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct EmergencyClaim {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64, // Unrecovered remainder of emergency withdrawals as of the last settle
+    pub bump: u8,
+    pub units: u128, // Share of Vault.emergency_claim_units
+    pub index_snapshot: u128, // Vault.emergency_claim_index at the last settle
+    pub epoch: u32, // Vault.emergency_claim_epoch at the last settle
+    pub pending: u64, // Recovered and awaiting redemption
+}
+
+impl EmergencyClaim {
+    /// Move the claim's part of liquidity recovered since its last settle into pending
+    pub fn settle(&mut self, vault: &Vault) {
+        let recovered = if self.epoch != vault.emergency_claim_epoch {
+            // Every claim of the earlier epoch has been repaid
+            self.units = 0;
+            self.epoch = vault.emergency_claim_epoch;
+            self.amount
+        } else {
+            let accrued = self.units.saturating_mul(vault.emergency_claim_index - self.index_snapshot)
+                / EMERGENCY_INDEX_PRECISION;
+            accrued.min(self.amount as u128) as u64
+        };
+        self.amount -= recovered;
+        self.pending += recovered;
+        self.index_snapshot = vault.emergency_claim_index;
+    }
+
+    /// Add an unpaid emergency remainder to a settled claim
+    pub fn add(&mut self, vault: &mut Vault, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        self.units += vault.record_emergency_claim(amount)?;
+        self.amount += amount;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalRequest {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub index: u64, // Position in the vault's FIFO queue
+    pub shares: u64, // Escrowed until fulfillment burns them
+    pub amount: u64, // Net payout, set on fulfillment
+    pub fee: u64,
+    pub status: WithdrawalStatus,
+    pub requested_at: i64,
+    pub claimable_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct YieldTracker {
+    pub vault: Pubkey,
+    #[max_len(10)]
+    pub chain_yields: Vec<ChainYield>,
+    pub last_update: i64,
+    pub query_nonce: u64,
+    pub bump: u8,
+    pub version: u8, // Schema version, see YIELD_TRACKER_VERSION
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InboundNonce {
+    pub vault: Pubkey,
+    pub src_chain_id: u16,
+    pub ordering: NonceOrdering,
+    pub last_nonce: u64, // Ordered: last nonce applied; Unordered: highest nonce applied
+    pub used_window: u64, // Unordered only: bit i set when last_nonce - i has been applied
+    pub bump: u8,
+}
+
+impl InboundNonce {
+    /// Accept `nonce` once, rejecting duplicates, gaps on ordered channels and nonces below the window
+    pub fn consume(&mut self, nonce: u64) -> Result<()> {
+        require!(nonce > 0, OmniVaultError::InvalidNonce);
+        match self.ordering {
+            NonceOrdering::Ordered => {
+                require!(self.last_nonce.checked_add(1) == Some(nonce), OmniVaultError::InvalidNonce);
+            }
+            NonceOrdering::Unordered => {
+                if nonce <= self.last_nonce {
+                    let offset = self.last_nonce - nonce;
+                    require!(offset < INBOUND_NONCE_WINDOW, OmniVaultError::InvalidNonce);
+                    require!(self.used_window & (1 << offset) == 0, OmniVaultError::InvalidNonce);
+                    self.used_window |= 1 << offset;
+                    return Ok(());
+                }
+                let shift = nonce - self.last_nonce;
+                self.used_window = if shift >= INBOUND_NONCE_WINDOW { 0 } else { self.used_window << shift };
+                self.used_window |= 1;
+            }
+        }
+        self.last_nonce = nonce;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ChainConfig {
+    pub chain_id: u16,
+    pub eid: u32, // LayerZero V2 endpoint id of chain_id, e.g. 30101 for Ethereum
+    pub executor_options: ExecutorOptions,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Peer {
+    pub chain_id: u16,
+    pub address: [u8; 32], // Trusted OApp on chain_id, as the bytes32 LayerZero reports as sender
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PendingConfig {
+    pub target: Pubkey, // Vault or VaultStore the change applies to
+    pub change: ConfigChange,
+    pub proposer: Pubkey,
+    pub queued_at: i64,
+    pub eta: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Roles {
+    pub vault_store: Pubkey,
+    #[max_len(10)]
+    pub keepers: Vec<Pubkey>,
+    #[max_len(5)]
+    pub guardians: Vec<Pubkey>,
+    #[max_len(5)]
+    pub fee_managers: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl VaultStore {
+    /// TVL entry of a mint, once any vault has held it or a cap has been set for it
+    pub fn mint_tvl(&self, mint: &Pubkey) -> Option<&MintTvl> {
+        self.mint_tvls.iter().find(|mint_tvl| mint_tvl.mint == *mint)
+    }
+
+    /// TVL entry of a mint, added on first use. Vaults and vault assets add theirs when they
+    /// are created, so deposits never run out of entries.
+    pub fn mint_tvl_mut(&mut self, mint: Pubkey) -> Result<&mut MintTvl> {
+        if let Some(index) = self.mint_tvls.iter().position(|mint_tvl| mint_tvl.mint == mint) {
+            return Ok(&mut self.mint_tvls[index]);
+        }
+        require!(self.mint_tvls.len() < MAX_TVL_MINTS, OmniVaultError::TooManyAssets);
+        self.mint_tvls.push(MintTvl {
+            mint,
+            total_tvl: 0,
+            deposit_cap: 0,
+        });
+        Ok(self.mint_tvls.last_mut().unwrap())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct MintTvl {
+    pub mint: Pubkey,
+    pub total_tvl: u64, // NAV of every vault and vault asset in this mint
+    pub deposit_cap: u64, // Maximum total_tvl accepted by deposits, 0 = uncapped
+}
+
+impl Roles {
+    pub fn has_role(&self, role: Role, key: &Pubkey) -> bool {
+        match role {
+            Role::Keeper => self.keepers.contains(key),
+            Role::Guardian => self.guardians.contains(key),
+            Role::FeeManager => self.fee_managers.contains(key),
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Multisig {
+    pub create_key: Pubkey,
+    #[max_len(10)]
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub signer_set_seqno: u32, // Bumped on every signer set change to invalidate old proposals
+    pub proposal_count: u64,
+    pub signer_bump: u8,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MultisigProposal {
+    pub multisig: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
+    #[max_len(16)]
+    pub accounts: Vec<ProposalAccount>,
+    #[max_len(512)]
+    pub data: Vec<u8>,
+    pub approvals: u16, // Bitmap indexed by position in Multisig.signers
+    pub signer_set_seqno: u32,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FeeTreasury {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub total_collected: u64,
+    pub bump: u8,
+}
+
+/// An additional asset of a vault. Its shares are an internal ledger kept in AssetPositions
+/// rather than SPL tokens: the vault's share mint prices the primary asset only, and each
+/// additional asset has its own NAV and share price. Exits follow the vault's lock period and
+/// early-exit penalty, and stop while the vault is in emergency exit mode. Only SPL vaults
+/// take additional assets.
+#[account]
+#[derive(InitSpace)]
+pub struct VaultAsset {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey, // Vault-owned PDA holding this asset
+    pub min_deposit: u64, // In this asset's base units
+    pub deposit_cap: u64, // Maximum NAV of this asset, 0 = uncapped
+    pub total_deposits: u64,
+    pub total_yield: u64,
+    pub total_shares: u64, // Claims on this asset's NAV held across AssetPositions
+    pub accrued_fees: u64, // Withdrawal, management and performance fees awaiting collect_asset_fees, excluded from NAV
+    pub high_water_mark: u64, // Highest share price of this asset fees have been charged at
+    pub last_fee_accrual: i64,
+    pub bump: u8,
+}
+
+impl VaultAsset {
+    /// Net asset value of this asset: principal plus realized yield
+    pub fn total_assets(&self) -> u64 {
+        self.total_deposits.saturating_add(self.total_yield)
+    }
+
+    /// Debit a redemption from principal and yield in proportion to their weight in NAV
+    pub fn debit_assets(&mut self, amount: u64) -> Result<()> {
+        let total_assets = self.total_assets();
+        require!(amount <= total_assets, OmniVaultError::InsufficientBalance);
+        if amount == 0 {
+            return Ok(());
+        }
+        let from_yield = (amount as u128 * self.total_yield as u128 / total_assets as u128) as u64;
+        self.total_yield -= from_yield;
+        self.total_deposits -= amount - from_yield;
+        Ok(())
+    }
 }
 
 #[account]
 #[derive(InitSpace)]
-pub struct YieldTracker {
-    pub vault: Pubkey,
-    #[max_len(10)]
-    pub chain_yields: Vec<ChainYield>,
-    pub last_update: i64,
-    pub query_nonce: u64,
+pub struct AssetPosition {
+    pub vault_asset: Pubkey,
+    pub user: Pubkey,
+    pub shares: u64, // Non-transferable claim on the VaultAsset's NAV, see VaultAsset
+    pub amount: u64, // Principal deposited, net of redemptions
+    pub last_deposit: i64,
+    pub last_withdrawal: i64,
     pub bump: u8,
 }
 
@@ -1277,6 +6722,12 @@ pub struct HookAction {
 }
 
 // Enums and Data Structures
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum AssetKind {
+    Spl, // Token base units held in Vault.token_account
+    NativeSol, // Lamports held on the vault PDA
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace, Debug)]
 pub enum RiskProfile {
     Conservative,
@@ -1284,6 +6735,63 @@ pub enum RiskProfile {
     Aggressive,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum ConfigChange {
+    Vault {
+        min_deposit: Option<u64>,
+        rebalance_threshold: Option<u64>,
+        #[max_len(10)]
+        target_chains: Option<Vec<u16>>,
+        management_fee_bps: Option<u16>,
+        performance_fee_bps: Option<u16>,
+        deposit_cap: Option<u64>,
+        lock_period: Option<i64>,
+        early_exit_penalty_bps: Option<u16>,
+    },
+    Store {
+        fee_rate: Option<u16>,
+        #[max_len(10)]
+        add_chains: Vec<u16>,
+        #[max_len(10)]
+        remove_chains: Vec<u16>,
+        config_delay: Option<i64>,
+        global_deposit_cap: Option<MintDepositCap>,
+    },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct MintDepositCap {
+    pub mint: Pubkey,
+    pub cap: u64, // Maximum TVL of the mint across vaults, 0 = uncapped
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ProposalAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace, Debug)]
+pub enum WithdrawalStatus {
+    Pending,
+    Fulfilled,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum NonceOrdering {
+    Ordered, // Nonces must arrive as last_nonce + 1
+    Unordered, // Any unused nonce within INBOUND_NONCE_WINDOW of the highest applied
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace, Debug)]
+pub enum Role {
+    Keeper,
+    Guardian,
+    FeeManager,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct ChainYield {
     pub chain_id: u16,
@@ -1293,11 +6801,104 @@ pub struct ChainYield {
     pub last_updated: i64,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace, Debug)]
+pub struct ExecutorOptions {
+    pub lz_receive_gas: u128,
+    pub lz_receive_value: u128, // Native value forwarded to lz_receive, 0 = none
+    pub native_drop_amount: u128, // Native airdrop on the destination, 0 = none
+    pub native_drop_receiver: [u8; 32],
+    pub compose_gas: u128, // Gas for lz_compose at index 0, 0 = no compose option
+    pub compose_value: u128,
+}
+
+impl ExecutorOptions {
+    /// Encode as LayerZero V2 type 3 options: a big-endian 0x0003 header followed by
+    /// the lzReceive, native drop and lzCompose executor options that are set
+    pub fn encode(&self) -> Vec<u8> {
+        let mut options = LZ_OPTIONS_TYPE_3.to_be_bytes().to_vec();
+        
+        let mut lz_receive = self.lz_receive_gas.to_be_bytes().to_vec();
+        if self.lz_receive_value > 0 {
+            lz_receive.extend_from_slice(&self.lz_receive_value.to_be_bytes());
+        }
+        push_executor_option(&mut options, LZ_OPTION_LZ_RECEIVE, &lz_receive);
+        
+        if self.native_drop_amount > 0 {
+            let mut native_drop = self.native_drop_amount.to_be_bytes().to_vec();
+            native_drop.extend_from_slice(&self.native_drop_receiver);
+            push_executor_option(&mut options, LZ_OPTION_NATIVE_DROP, &native_drop);
+        }
+        
+        if self.compose_gas > 0 {
+            let mut compose = 0u16.to_be_bytes().to_vec();
+            compose.extend_from_slice(&self.compose_gas.to_be_bytes());
+            if self.compose_value > 0 {
+                compose.extend_from_slice(&self.compose_value.to_be_bytes());
+            }
+            push_executor_option(&mut options, LZ_OPTION_LZ_COMPOSE, &compose);
+        }
+        
+        options
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ReceiveMessageParams {
+    pub message: Vec<u8>,
+    pub attestation: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DepositForBurnParams {
+    pub amount: u64,
+    pub destination_domain: u32,
+    pub mint_recipient: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LzReceiveParams {
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+    pub message: Vec<u8>,
+    pub extra_data: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LzClearParams {
+    pub receiver: Pubkey,
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+    pub message: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LzQuoteParams {
+    pub sender: Pubkey,
+    pub dst_eid: u32,
+    pub receiver: [u8; 32],
+    pub message: Vec<u8>,
+    pub options: Vec<u8>,
+    pub pay_in_lz_token: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MessagingFee {
+    pub native_fee: u64,
+    pub lz_token_fee: u64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct LzSendData {
-    pub dst_chain_id: u16,
+pub struct LzSendParams {
+    pub dst_eid: u32,
+    pub receiver: [u8; 32],
     pub message: Vec<u8>,
     pub options: Vec<u8>,
+    pub native_fee: u64,
+    pub lz_token_fee: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -1311,16 +6912,19 @@ pub struct CrossChainMessage {
 pub enum CrossChainAction {
     Rebalance {
         vault_id: u64,
+        vault: Pubkey,
         new_allocation: Vec<u8>,
     },
     YieldQuery {
         vault_id: u64,
+        vault: Pubkey,
         risk_profile: RiskProfile,
         query_nonce: u64,
         requested_chains: Vec<u16>,
     },
     YieldResponse {
         vault_id: u64,
+        vault: Pubkey,
         chain_id: u16,
         apy: u64,
         tvl: u64,
@@ -1329,93 +6933,398 @@ pub enum CrossChainAction {
     },
     EmergencyPause {
         vault_id: u64,
+        vault: Pubkey,
     },
 }
 
-// Events for real-time frontend updates
+// Events for real-time frontend updates
+#[event]
+pub struct VaultCreated {
+    pub vault_id: u64,
+    pub owner: Pubkey,
+    pub risk_profile: RiskProfile,
+    pub target_chains: Vec<u16>,
+}
+
+#[event]
+pub struct DepositMade {
+    pub vault_id: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub new_total: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct WithdrawalMade {
+    pub vault_id: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub shares: u64,
+    pub penalty: u64, // Early-exit penalty left in the vault for remaining holders
+}
+
+#[event]
+pub struct EmergencyWithdrawal {
+    pub vault_id: u64,
+    pub user: Pubkey,
+    pub shares: u64,
+    pub paid: u64,
+    pub claimed: u64, // Remainder recorded on the user's EmergencyClaim
+}
+
+#[event]
+pub struct EmergencyClaimRedeemed {
+    pub vault_id: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct PositionClosed {
+    pub vault_id: u64,
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct VaultAssetRemoved {
+    pub vault_id: u64,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct VaultClosed {
+    pub vault_id: u64,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalRequested {
+    pub vault_id: u64,
+    pub user: Pubkey,
+    pub index: u64,
+    pub shares: u64,
+    pub queue_position: u64, // Requests ahead of this one
+}
+
+#[event]
+pub struct WithdrawalFulfilled {
+    pub vault_id: u64,
+    pub user: Pubkey,
+    pub index: u64,
+    pub amount: u64,
+    pub fee: u64,
+    pub claimable_at: i64,
+    pub remaining_shares: u64, // Still escrowed after a partial fill
+}
+
+#[event]
+pub struct WithdrawalCancelled {
+    pub vault_id: u64,
+    pub user: Pubkey,
+    pub index: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct WithdrawalClaimed {
+    pub vault_id: u64,
+    pub user: Pubkey,
+    pub index: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct YieldHarvested {
+    pub vault_id: u64,
+    pub amount: u64,
+    pub total_yield: u64,
+    pub share_price: u64,
+}
+
+#[event]
+pub struct YieldQuerySent {
+    pub vault_id: u64,
+    pub chains: Vec<u16>,
+    pub nonce: u64,
+    pub native_fee: u64, // Lamports the payer spent on the sends
+}
+
+#[event]
+pub struct ChainConfigUpdated {
+    pub chain_id: u16,
+    pub eid: u32,
+    pub lz_receive_gas: u128,
+    pub updated_by: Pubkey,
+}
+
+#[event]
+pub struct PeerSet {
+    pub chain_id: u16,
+    pub address: [u8; 32],
+    pub set_by: Pubkey,
+}
+
+#[event]
+pub struct YieldDataReceived {
+    pub vault_id: u64,
+    pub chain_id: u16,
+    pub apy: u64,
+    pub tvl: u64,
+    pub risk_score: u64,
+}
+
+#[event]
+pub struct RebalanceTriggered {
+    pub vault_id: u64,
+    pub from_chain: u16,
+    pub to_chain: u16,
+    pub yield_improvement: u64,
+}
+
+#[event]
+pub struct RebalanceExecuted {
+    pub vault_id: u64,
+    pub allocation_data: Vec<u8>,
+}
+
+#[event]
+pub struct ManualRebalance {
+    pub vault_id: u64,
+    pub target_chain: u16,
+    pub executor: Pubkey,
+}
+
+#[event]
+pub struct EmergencyPauseActivated {
+    pub vault_id: u64,
+    pub triggered_by_chain: u16,
+}
+
+#[event]
+pub struct PauseFlagsChanged {
+    pub vault: Option<Pubkey>, // None for the store-wide flags
+    pub previous_flags: u8,
+    pub pause_flags: u8,
+    pub changed_by: Pubkey,
+}
+
+#[event]
+pub struct EmergencyExitChanged {
+    pub vault_id: u64,
+    pub emergency_exit: bool,
+    pub changed_by: Pubkey,
+}
+
+#[event]
+pub struct VaultConfigUpdated {
+    pub vault_id: u64,
+    pub updated_by: Pubkey,
+}
+
+#[event]
+pub struct StoreConfigUpdated {
+    pub fee_rate: u16,
+    pub supported_chains: Vec<u16>,
+    pub config_delay: i64,
+    pub global_deposit_cap: Option<MintDepositCap>,
+    pub updated_by: Pubkey,
+}
+
+#[event]
+pub struct ConfigChangeQueued {
+    pub target: Pubkey,
+    pub proposer: Pubkey,
+    pub eta: i64,
+}
+
+#[event]
+pub struct ConfigChangeCancelled {
+    pub target: Pubkey,
+    pub cancelled_by: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferProposed {
+    pub current_authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct VaultOwnershipProposed {
+    pub vault_id: u64,
+    pub current_owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct VaultOwnershipTransferred {
+    pub vault_id: u64,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub role: Role,
+    pub member: Pubkey,
+    pub granted_by: Pubkey,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub role: Role,
+    pub member: Pubkey,
+    pub revoked_by: Pubkey,
+}
+
+#[event]
+pub struct MultisigCreated {
+    pub multisig: Pubkey,
+    pub multisig_signer: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct MultisigChanged {
+    pub multisig: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
 #[event]
-pub struct VaultCreated {
-    pub vault_id: u64,
-    pub owner: Pubkey,
-    pub risk_profile: RiskProfile,
-    pub target_chains: Vec<u16>,
+pub struct MultisigProposalCreated {
+    pub multisig: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
 }
 
 #[event]
-pub struct DepositMade {
-    pub vault_id: u64,
-    pub user: Pubkey,
-    pub amount: u64,
-    pub new_total: u64,
+pub struct MultisigProposalApproved {
+    pub multisig: Pubkey,
+    pub index: u64,
+    pub signer: Pubkey,
+    pub approvals: u8,
 }
 
 #[event]
-pub struct WithdrawalMade {
-    pub vault_id: u64,
-    pub user: Pubkey,
-    pub amount: u64,
-    pub fee: u64,
+pub struct MultisigProposalExecuted {
+    pub multisig: Pubkey,
+    pub index: u64,
+    pub executed_by: Pubkey,
 }
 
 #[event]
-pub struct YieldQuerySent {
-    pub vault_id: u64,
-    pub chains: Vec<u16>,
-    pub nonce: u64,
+pub struct SystemEmergencyPause {
+    pub triggered_by: Pubkey,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct YieldDataReceived {
+pub struct SystemResumed {
+    pub resumed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesCollected {
     pub vault_id: u64,
-    pub chain_id: u16,
-    pub apy: u64,
-    pub tvl: u64,
-    pub risk_score: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub total_collected: u64,
+    pub collected_by: Pubkey,
 }
 
 #[event]
-pub struct RebalanceTriggered {
+pub struct TreasuryWithdrawn {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub withdrawn_by: Pubkey,
+}
+
+#[event]
+pub struct VaultFeesUpdated {
     pub vault_id: u64,
-    pub from_chain: u16,
-    pub to_chain: u16,
-    pub yield_improvement: u64,
+    pub management_fee_bps: u16,
+    pub performance_fee_bps: u16,
+    pub updated_by: Pubkey,
 }
 
 #[event]
-pub struct RebalanceExecuted {
+pub struct FeesAccrued {
     pub vault_id: u64,
-    pub allocation_data: Vec<u8>,
+    pub management_fee: u64,
+    pub performance_fee: u64,
+    pub high_water_mark: u64,
+    pub share_price: u64,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct ManualRebalance {
+pub struct VaultAssetAdded {
     pub vault_id: u64,
-    pub target_chain: u16,
-    pub executor: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub min_deposit: u64,
+    pub deposit_cap: u64,
 }
 
 #[event]
-pub struct EmergencyPauseActivated {
+pub struct AssetFeesAccrued {
     pub vault_id: u64,
-    pub triggered_by_chain: u16,
+    pub mint: Pubkey,
+    pub management_fee: u64,
+    pub performance_fee: u64,
+    pub high_water_mark: u64,
+    pub share_price: u64,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct VaultConfigUpdated {
+pub struct AssetDepositMade {
     pub vault_id: u64,
-    pub updated_by: Pubkey,
+    pub mint: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub total_assets: u64,
 }
 
 #[event]
-pub struct SystemEmergencyPause {
-    pub triggered_by: Pubkey,
-    pub timestamp: i64,
+pub struct AssetWithdrawalMade {
+    pub vault_id: u64,
+    pub mint: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub shares: u64,
+    pub penalty: u64, // Early-exit penalty left in the asset for remaining holders
 }
 
 #[event]
-pub struct SystemResumed {
-    pub resumed_by: Pubkey,
-    pub timestamp: i64,
+pub struct AssetYieldHarvested {
+    pub vault_id: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub total_yield: u64,
+    pub share_price: u64,
 }
 
 // CCTP Events
@@ -1427,6 +7336,7 @@ pub struct CCTPDepositMade {
     pub amount: u64,
     pub source_domain: u32,
     pub new_total: u64,
+    pub shares: u64,
 }
 
 #[event]
@@ -1436,6 +7346,8 @@ pub struct CCTPWithdrawalMade {
     pub amount: u64,
     pub fee: u64,
     pub destination_domain: u32,
+    pub shares: u64,
+    pub penalty: u64,
 }
 
 #[event]
@@ -1516,5 +7428,1007 @@ pub enum OmniVaultError {
     FastTransferNotEligible,
     #[msg("Domain not supported")]
     DomainNotSupported,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("No fees to collect")]
+    NoFeesToCollect,
+    #[msg("Fee exceeds protocol maximum")]
+    FeeTooHigh,
+    #[msg("Chain not supported")]
+    UnsupportedChain,
+    #[msg("Role already granted")]
+    RoleAlreadyGranted,
+    #[msg("Role not granted")]
+    RoleNotGranted,
+    #[msg("Too many role members")]
+    TooManyRoleMembers,
+    #[msg("Timelock has not expired")]
+    TimelockNotExpired,
+    #[msg("Invalid timelock delay")]
+    InvalidTimelockDelay,
+    #[msg("Invalid configuration change")]
+    InvalidConfigChange,
+    #[msg("Invalid multisig signers")]
+    InvalidMultisigSigners,
+    #[msg("Proposal already executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Proposal created under an old signer set")]
+    StaleProposal,
+    #[msg("Not enough approvals")]
+    NotEnoughApprovals,
+    #[msg("Deposit would exceed the vault deposit cap")]
+    DepositCapExceeded,
+    #[msg("Deposit would exceed the global deposit cap")]
+    GlobalDepositCapExceeded,
+    #[msg("Asset already added to vault")]
+    AssetAlreadyAdded,
+    #[msg("Too many assets")]
+    TooManyAssets,
+    #[msg("Instruction does not match the vault asset kind")]
+    AssetKindMismatch,
+    #[msg("Withdrawal would leave the vault below its rent-exempt minimum")]
+    RentExemptFloor,
+    #[msg("Insufficient liquidity on Solana, request a queued withdrawal")]
+    InsufficientLiquidity,
+    #[msg("Withdrawal request is not in the expected state")]
+    InvalidWithdrawalStatus,
+    #[msg("Withdrawal requests must be fulfilled in queue order")]
+    WithdrawalOutOfOrder,
+    #[msg("Withdrawal settlement period has not elapsed")]
+    SettlementPending,
+    #[msg("Position is inside the vault lock period")]
+    PositionLocked,
+    #[msg("Invalid lock period")]
+    InvalidLockPeriod,
+    #[msg("Vault is not in emergency exit mode")]
+    VaultNotInEmergencyExit,
+    #[msg("Nothing to redeem")]
+    NothingToRedeem,
+    #[msg("Operation is paused")]
+    OperationPaused,
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags,
+    #[msg("Position still holds shares")]
+    PositionNotEmpty,
+    #[msg("Vault still carries shares, assets or pending withdrawals")]
+    VaultNotEmpty,
+    #[msg("Vault must be deactivated before it is closed")]
+    VaultStillActive,
+    #[msg("A token account is required to receive the residual balance")]
+    SweepAccountRequired,
+    #[msg("Account is not at the expected schema version")]
+    AccountVersionMismatch,
+    #[msg("Message sender is not the registered peer for its source chain")]
+    UnauthorizedPeer,
+    #[msg("Invalid peer address")]
+    InvalidPeer,
+    #[msg("Message reports data for a chain other than its source")]
+    InvalidSourceChain,
+    #[msg("lz_receive must clear its payload with the LayerZero endpoint")]
+    NotCalledByEndpoint,
+    #[msg("Each target chain needs its ChainConfig, in order")]
+    MissingChainConfig,
+    #[msg("Invalid executor options")]
+    InvalidExecutorOptions,
+    #[msg("LayerZero endpoint returned no valid fee quote")]
+    InvalidFeeQuote,
+    #[msg("Quoted messaging fee exceeds the maximum")]
+    MessagingFeeTooHigh,
+    #[msg("CCTP mint recipient must be a 32-byte address")]
+    InvalidMintRecipient,
+    #[msg("lz_receive must be reached through a CPI, not as a top-level instruction")]
+    LzReceiveNotInvokedByCpi,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn lz_receive_only(gas: u128) -> ExecutorOptions {
+        ExecutorOptions {
+            lz_receive_gas: gas,
+            lz_receive_value: 0,
+            native_drop_amount: 0,
+            native_drop_receiver: [0u8; 32],
+            compose_gas: 0,
+            compose_value: 0,
+        }
+    }
+
+    fn test_vault() -> Vault {
+        Vault {
+            id: 1,
+            owner: Pubkey::default(),
+            risk_profile: RiskProfile::Moderate,
+            total_deposits: 0,
+            total_yield: 0,
+            min_deposit: 1,
+            is_active: true,
+            last_rebalance: 0,
+            target_chains: vec![],
+            current_best_chain: 0,
+            current_apy: 0,
+            rebalance_threshold: 200,
+            emergency_exit: false,
+            bump: 255,
+            share_mint: Pubkey::default(),
+            share_price: PRICE_PRECISION,
+            accrued_fees: 0,
+            management_fee_bps: 0,
+            performance_fee_bps: 0,
+            high_water_mark: PRICE_PRECISION,
+            last_fee_accrual: 0,
+            creator: Pubkey::default(),
+            pending_owner: Pubkey::default(),
+            deposit_cap: 0,
+            asset_mint: Pubkey::default(),
+            token_account: Pubkey::default(),
+            extra_assets: vec![],
+            asset_kind: AssetKind::Spl,
+            withdrawal_queue_head: 0,
+            withdrawal_queue_tail: 0,
+            reserved_withdrawals: 0,
+            lock_period: 0,
+            early_exit_penalty_bps: 0,
+            emergency_claims: 0,
+            emergency_claim_units: 0,
+            emergency_claim_index: 0,
+            emergency_claim_epoch: 0,
+            emergency_reserved: 0,
+            emergency_synced_liquidity: 0,
+            pause_flags: 0,
+            version: VAULT_VERSION,
+        }
+    }
+
+    #[test]
+    fn accrues_management_fee_pro_rata() {
+        let mut vault = test_vault();
+        vault.total_deposits = 1_000_000_000;
+        vault.management_fee_bps = 200;
+        accrue_fees_at(&mut vault, 1_000_000_000, SECONDS_PER_YEAR / 2).unwrap();
+        // 2% a year for half a year
+        assert_eq!(vault.accrued_fees, 10_000_000);
+        assert_eq!(vault.total_assets(), 990_000_000);
+        assert_eq!(vault.share_price, 990_000_000);
+        assert_eq!(vault.last_fee_accrual, SECONDS_PER_YEAR / 2);
+    }
+
+    #[test]
+    fn truncated_management_fee_keeps_accruing() {
+        let mut vault = test_vault();
+        vault.total_deposits = 1_000_000;
+        vault.management_fee_bps = 100;
+        // 1% a year on 1e6 units is under one unit per 3000 seconds
+        for now in (60..=3600).step_by(60) {
+            accrue_fees_at(&mut vault, 1_000_000, now).unwrap();
+        }
+        assert_eq!(vault.accrued_fees, 1);
+        assert_eq!(vault.last_fee_accrual, 3180);
+    }
+
+    #[test]
+    fn performance_fee_only_above_high_water_mark() {
+        let mut vault = test_vault();
+        vault.total_deposits = 1_000_000_000;
+        vault.total_yield = 100_000_000;
+        vault.performance_fee_bps = 1000;
+        accrue_fees_at(&mut vault, 1_000_000_000, 0).unwrap();
+        // 10% of the 0.1 gain per share
+        assert_eq!(vault.accrued_fees, 10_000_000);
+        assert_eq!(vault.total_yield, 90_000_000);
+        assert_eq!(vault.high_water_mark, 1_090_000_000);
+        
+        // A drawdown and a recovery back to the mark charge nothing
+        vault.total_yield = 50_000_000;
+        accrue_fees_at(&mut vault, 1_000_000_000, 0).unwrap();
+        vault.total_yield = 90_000_000;
+        accrue_fees_at(&mut vault, 1_000_000_000, 0).unwrap();
+        assert_eq!(vault.accrued_fees, 10_000_000);
+        assert_eq!(vault.high_water_mark, 1_090_000_000);
+        
+        // Only the gain past the mark is charged
+        vault.total_yield = 190_000_000;
+        accrue_fees_at(&mut vault, 1_000_000_000, 0).unwrap();
+        assert_eq!(vault.accrued_fees, 20_000_000);
+        assert_eq!(vault.high_water_mark, 1_180_000_000);
+    }
+
+    #[test]
+    fn accrues_vault_fee_rates_on_additional_assets() {
+        let mut vault = test_vault();
+        vault.management_fee_bps = 200;
+        vault.performance_fee_bps = 1000;
+        let mut vault_asset = VaultAsset {
+            vault: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            min_deposit: 1,
+            deposit_cap: 0,
+            total_deposits: 1_000_000_000,
+            total_yield: 0,
+            total_shares: 1_000_000_000,
+            accrued_fees: 0,
+            high_water_mark: PRICE_PRECISION,
+            last_fee_accrual: 0,
+            bump: 255,
+        };
+        // 2% a year for half a year on the asset's own NAV; the vault itself is not charged
+        accrue_asset_fees_at(&vault, &mut vault_asset, SECONDS_PER_YEAR / 2).unwrap();
+        assert_eq!(vault_asset.accrued_fees, 10_000_000);
+        assert_eq!(vault_asset.total_assets(), 990_000_000);
+        assert_eq!(vault.accrued_fees, 0);
+        
+        // Only the gain past the asset's high-water mark pays the performance fee
+        vault_asset.total_yield = 110_000_000;
+        accrue_asset_fees_at(&vault, &mut vault_asset, SECONDS_PER_YEAR / 2).unwrap();
+        assert_eq!(vault_asset.accrued_fees, 20_000_000);
+        assert_eq!(vault_asset.total_yield, 100_000_000);
+        assert_eq!(vault_asset.high_water_mark, 1_090_000_000);
+    }
+
+    #[test]
+    fn fills_queued_withdrawals_up_to_liquidity() {
+        assert_eq!(fillable_shares(1_000, 2_000, 5_000), 1_000);
+        assert_eq!(fillable_shares(1_000, 2_000, 2_000), 1_000);
+        // Half the payout on hand fills half the shares, rounding down
+        assert_eq!(fillable_shares(1_000, 2_000, 1_000), 500);
+        assert_eq!(fillable_shares(1_000, 3_000, 1_000), 333);
+        assert_eq!(fillable_shares(1_000, 2_000, 0), 0);
+    }
+
+    #[test]
+    fn withdrawal_fee_does_not_overflow_on_large_amounts() {
+        assert_eq!(withdrawal_fee(10_000, 100).unwrap(), 100);
+        assert_eq!(withdrawal_fee(u64::MAX, 1000).unwrap(), u64::MAX / 10);
+        assert_eq!(withdrawal_fee(99, 100).unwrap(), 0);
+    }
+
+    #[test]
+    fn token_liquidity_excludes_reserved_and_fees() {
+        let mut vault = test_vault();
+        vault.reserved_withdrawals = 300;
+        vault.accrued_fees = 200;
+        assert_eq!(token_liquidity(&vault, 1_000), 500);
+        assert_eq!(token_liquidity(&vault, 400), 0);
+    }
+
+    #[test]
+    fn fee_liquidity_keeps_withdrawal_and_emergency_reserves() {
+        let mut vault = test_vault();
+        vault.reserved_withdrawals = 300;
+        vault.emergency_reserved = 100;
+        vault.accrued_fees = 200;
+        assert_eq!(fee_liquidity(&vault, 1_000), 600);
+        assert_eq!(fee_liquidity(&vault, 500), 100);
+        assert_eq!(fee_liquidity(&vault, 350), 0);
+    }
+
+    fn test_store() -> VaultStore {
+        VaultStore {
+            authority: Pubkey::default(),
+            total_vaults: 2,
+            legacy_total_tvl: 0,
+            fee_rate: 100,
+            bump: 255,
+            last_global_rebalance: 0,
+            emergency_pause: false,
+            supported_chains: vec![],
+            pending_authority: Pubkey::default(),
+            config_delay: DEFAULT_CONFIG_DELAY,
+            mint_tvls: vec![],
+            pause_flags: 0,
+            version: VAULT_STORE_VERSION,
+        }
+    }
+
+    #[test]
+    fn global_deposit_cap_counts_only_the_vault_mint() {
+        let (usdc, wsol) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut store = test_store();
+        store.mint_tvl_mut(wsol).unwrap().deposit_cap = 1_000;
+        sync_tvl(&mut store, usdc, 0, 1_000_000_000).unwrap();
+
+        let mut sol_vault = test_vault();
+        sol_vault.asset_mint = wsol;
+        sol_vault.total_deposits = 400;
+        sync_tvl(&mut store, wsol, 0, 400).unwrap();
+        let mut other_sol_vault = test_vault();
+        other_sol_vault.asset_mint = wsol;
+        other_sol_vault.total_deposits = 500;
+        sync_tvl(&mut store, wsol, 0, 500).unwrap();
+
+        // USDC TVL far above the wSOL cap does not count against it
+        check_deposit_caps(&sol_vault, &store, 400, 100).unwrap();
+        assert_eq!(
+            check_deposit_caps(&sol_vault, &store, 400, 101).unwrap_err(),
+            OmniVaultError::GlobalDepositCapExceeded.into()
+        );
+
+        // Withdrawals free up room under the cap
+        other_sol_vault.total_deposits = 0;
+        sync_tvl(&mut store, wsol, 500, 0).unwrap();
+        check_deposit_caps(&other_sol_vault, &store, 0, 600).unwrap();
+        let mut usdc_vault = test_vault();
+        usdc_vault.asset_mint = usdc;
+        check_deposit_caps(&usdc_vault, &store, 0, u64::MAX / 2).unwrap();
+        assert_eq!(store.mint_tvl(&wsol).unwrap().total_tvl, 400);
+    }
+
+    #[test]
+    fn registered_mints_keep_syncing_once_the_tvl_list_is_full() {
+        let mut store = test_store();
+        let registered = Pubkey::new_unique();
+        store.mint_tvl_mut(registered).unwrap();
+        while store.mint_tvls.len() < MAX_TVL_MINTS {
+            store.mint_tvl_mut(Pubkey::new_unique()).unwrap();
+        }
+
+        // A new vault mint is rejected up front, while known mints still sync
+        assert_eq!(
+            store.mint_tvl_mut(Pubkey::new_unique()).err(),
+            Some(OmniVaultError::TooManyAssets.into())
+        );
+        sync_tvl(&mut store, registered, 0, 250).unwrap();
+        assert_eq!(store.mint_tvl(&registered).unwrap().total_tvl, 250);
+    }
+
+    #[test]
+    fn migrates_legacy_vault_with_shrunk_target_chains() {
+        let owner = Pubkey::new_unique();
+        let legacy = |target_chains: Vec<u16>| VaultV0 {
+            id: 7,
+            owner,
+            risk_profile: RiskProfile::Aggressive,
+            total_deposits: 5_000,
+            total_yield: 250,
+            min_deposit: 100,
+            is_active: true,
+            last_rebalance: 1_700_000_000,
+            target_chains,
+            current_best_chain: 110,
+            current_apy: 800,
+            rebalance_threshold: 200,
+            emergency_exit: false,
+            bump: 254,
+        };
+        // Written at creation, then rewritten in place with a shorter chain list
+        let mut data = vec![0u8; 8 + VaultV0::INIT_SPACE];
+        data[..8].copy_from_slice(Vault::DISCRIMINATOR);
+        legacy(vec![101, 110, 109]).serialize(&mut &mut data[8..]).unwrap();
+        legacy(vec![110]).serialize(&mut &mut data[8..]).unwrap();
+        let stale_start = 8 + legacy(vec![110]).try_to_vec().unwrap().len();
+        assert!(data[stale_start..].iter().any(|byte| *byte != 0));
+
+        let (share_mint, asset_mint, token_account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let vault = VaultV0::try_from_account_data(&data)
+            .unwrap()
+            .into_vault(share_mint, asset_mint, token_account, AssetKind::Spl, 1_800_000_000);
+        assert_eq!(vault.target_chains, vec![110]);
+        assert_eq!((vault.id, vault.owner, vault.creator, vault.bump), (7, owner, owner, 254));
+        assert_eq!((vault.total_deposits, vault.total_yield, vault.min_deposit), (5_000, 250, 100));
+        assert_eq!((vault.share_mint, vault.asset_mint, vault.token_account), (share_mint, asset_mint, token_account));
+        assert_eq!((vault.share_price, vault.last_fee_accrual, vault.version), (PRICE_PRECISION, 1_800_000_000, VAULT_VERSION));
+
+        // Resized and rewritten, the account reads back in the current layout
+        data.resize(8 + Vault::INIT_SPACE, 0);
+        vault.try_serialize(&mut &mut data[..]).unwrap();
+        let migrated = Vault::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!((migrated.share_mint, migrated.asset_kind, migrated.pause_flags), (share_mint, AssetKind::Spl, 0));
+
+        data[0] ^= 1;
+        assert!(VaultV0::try_from_account_data(&data).is_err());
+    }
+
+    fn test_position() -> UserPosition {
+        UserPosition {
+            vault: Pubkey::default(),
+            user: Pubkey::default(),
+            amount: 0,
+            last_deposit: 0,
+            last_withdrawal: 0,
+            bump: 255,
+            shares: 0,
+            locked_shares: 0,
+            version: USER_POSITION_VERSION,
+        }
+    }
+
+    fn locked_vault() -> Vault {
+        let mut vault = test_vault();
+        vault.lock_period = 1_000;
+        vault.early_exit_penalty_bps = 200;
+        vault
+    }
+
+    #[test]
+    fn early_exit_penalty_is_credited_to_holders() {
+        let mut vault = locked_vault();
+        vault.total_deposits = 2_000;
+        let mut position = test_position();
+        position.record_deposit(&vault, 0, 1_000, 0);
+
+        // Half way through the lock, 2% decays to 1%
+        let penalty = position.redeem_shares(&vault, 1_000, 1_000, 1_000, 500).unwrap();
+        assert_eq!(penalty, 10);
+        vault.debit_exit(1_000, penalty).unwrap();
+        assert_eq!((vault.total_deposits, vault.total_yield), (1_000, 10));
+        assert_eq!((position.shares, position.locked_shares), (0, 0));
+    }
+
+    #[test]
+    fn transferred_shares_pay_the_full_penalty() {
+        let vault = locked_vault();
+        let mut sender = test_position();
+        sender.record_deposit(&vault, 0, 1_000, 0);
+
+        // A fresh wallet holding the sender's shares has no deposit of its own
+        let mut receiver = test_position();
+        assert_eq!(receiver.redeem_shares(&vault, 1_000, 1_000, 1_000, 900).unwrap(), 20);
+
+        // Own unlocked shares go first, received ones pay in full
+        receiver.record_deposit(&vault, 0, 500, 0);
+        assert_eq!(receiver.locked_split(&vault, 800, 800, 1_000), (0, 300));
+        assert_eq!(receiver.redeem_shares(&vault, 800, 800, 800, 1_000).unwrap(), 6);
+
+        let mut hard_locked = locked_vault();
+        hard_locked.early_exit_penalty_bps = 0;
+        assert!(test_position().redeem_shares(&hard_locked, 1_000, 1_000, 1_000, 900).is_err());
+    }
+
+    #[test]
+    fn top_up_weights_the_unlock_time() {
+        let vault = locked_vault();
+        let mut position = test_position();
+        position.record_deposit(&vault, 0, 300, 0);
+        position.record_deposit(&vault, 300, 100, 400);
+        // 300 shares from t=0 and 100 from t=400 unlock together at t=1100
+        assert_eq!((position.last_deposit, position.locked_shares), (100, 400));
+        assert_eq!(position.locked_split(&vault, 400, 400, 1_099), (400, 0));
+        assert_eq!(position.locked_split(&vault, 400, 400, 1_100), (0, 0));
+
+        // Once unlocked, a top-up locks only the new shares
+        position.record_deposit(&vault, 400, 100, 2_000);
+        assert_eq!((position.last_deposit, position.locked_shares, position.shares), (2_000, 100, 500));
+        assert_eq!(position.locked_split(&vault, 500, 450, 2_500), (50, 0));
+
+        // Shares received by transfer are taken in as deposited now
+        position.record_deposit(&vault, 700, 100, 2_000);
+        assert_eq!((position.locked_shares, position.shares), (400, 800));
+    }
+
+    fn test_claim() -> EmergencyClaim {
+        EmergencyClaim {
+            vault: Pubkey::default(),
+            user: Pubkey::default(),
+            amount: 0,
+            bump: 255,
+            units: 0,
+            index_snapshot: 0,
+            epoch: 0,
+            pending: 0,
+        }
+    }
+
+    // Mirrors emergency_withdraw for a holder redeeming `entitlement` worth of shares
+    fn emergency_exit(vault: &mut Vault, claim: &mut EmergencyClaim, liquidity: u64, entitlement: u64) -> u64 {
+        vault.sync_emergency_liquidity(liquidity).unwrap();
+        let paid = vault.emergency_exit_payout(entitlement).unwrap();
+        vault.debit_assets(entitlement).unwrap();
+        claim.settle(vault);
+        claim.add(vault, entitlement - paid).unwrap();
+        paid
+    }
+
+    #[test]
+    fn emergency_claim_redeems_only_new_recoveries() {
+        let mut vault = test_vault();
+        vault.total_deposits = 1_000;
+        vault.enter_emergency();
+        let mut claim = test_claim();
+
+        // 300 on hand for 1000 of NAV pays 30%
+        assert_eq!(emergency_exit(&mut vault, &mut claim, 300, 600), 180);
+        assert_eq!((claim.amount, vault.emergency_claims, vault.total_assets()), (420, 420, 400));
+
+        // Nothing recovered yet, however often it is called
+        assert_eq!(emergency_redemption(&mut vault, &mut claim, 120).unwrap(), 0);
+        assert_eq!(emergency_redemption(&mut vault, &mut claim, 120).unwrap(), 0);
+
+        // 410 recovered is split 420:400 between claims and remaining holders
+        assert_eq!(emergency_redemption(&mut vault, &mut claim, 530).unwrap(), 210);
+        assert_eq!((claim.amount, vault.emergency_claims, vault.emergency_reserved), (210, 210, 0));
+        assert_eq!(vault.emergency_synced_liquidity, 320);
+
+        // The same liquidity cannot be redeemed twice
+        let liquidity = token_liquidity(&vault, 320);
+        assert_eq!(emergency_redemption(&mut vault, &mut claim, liquidity).unwrap(), 0);
+    }
+
+    #[test]
+    fn emergency_claims_of_different_vintages_share_recoveries() {
+        let mut vault = test_vault();
+        vault.total_deposits = 1_000;
+        vault.enter_emergency();
+        let (mut early, mut late) = (test_claim(), test_claim());
+
+        emergency_exit(&mut vault, &mut early, 300, 600);
+        assert_eq!(emergency_redemption(&mut vault, &mut early, 530).unwrap(), 210);
+        // The last holder exits against the 320 left to holders, leaving 80 unpaid
+        assert_eq!(emergency_exit(&mut vault, &mut late, 320, 400), 320);
+        assert_eq!(late.amount, 80);
+        assert_eq!(vault.emergency_claims, 290);
+
+        // With no NAV left, recoveries go to claims in proportion to what they are owed
+        // Each call sees liquidity net of what has already been set aside for claims
+        assert_eq!(emergency_redemption(&mut vault, &mut early, 145).unwrap(), 105);
+        assert_eq!(emergency_redemption(&mut vault, &mut late, 0).unwrap(), 40);
+        assert_eq!((early.amount, late.amount, vault.emergency_claims), (105, 40, 145));
+
+        // Full repayment starts a new epoch and settles both claims in full
+        assert_eq!(emergency_redemption(&mut vault, &mut late, 145).unwrap(), 40);
+        assert_eq!((vault.emergency_claims, vault.emergency_claim_units, vault.emergency_claim_epoch), (0, 0, 1));
+        assert_eq!(emergency_redemption(&mut vault, &mut early, 0).unwrap(), 105);
+        assert_eq!((early.amount, late.amount, vault.emergency_reserved), (0, 0, 0));
+
+        // A claim opened in the new epoch starts from fresh units
+        let mut next = test_claim();
+        next.settle(&vault);
+        next.add(&mut vault, 50).unwrap();
+        assert_eq!((next.units, vault.emergency_claim_units), (50, 50));
+    }
+
+    #[test]
+    fn emergency_claim_repaid_at_par_after_emergency() {
+        let mut vault = test_vault();
+        vault.total_deposits = 1_000;
+        vault.enter_emergency();
+        let mut claim = test_claim();
+        emergency_exit(&mut vault, &mut claim, 300, 600);
+        vault.emergency_exit = false;
+
+        assert_eq!(emergency_redemption(&mut vault, &mut claim, 100).unwrap(), 100);
+        assert_eq!((claim.amount, vault.emergency_claims), (320, 320));
+        assert_eq!(emergency_redemption(&mut vault, &mut claim, 1_000).unwrap(), 320);
+        assert_eq!((claim.amount, vault.emergency_claims, vault.emergency_claim_units), (0, 0, 0));
+    }
+
+    fn inbound_nonce(ordering: NonceOrdering) -> InboundNonce {
+        InboundNonce {
+            vault: Pubkey::new_unique(),
+            src_chain_id: 101,
+            ordering,
+            last_nonce: 0,
+            used_window: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn ordered_nonces_reject_duplicates_and_gaps() {
+        let mut nonces = inbound_nonce(NonceOrdering::Ordered);
+        nonces.consume(1).unwrap();
+        nonces.consume(2).unwrap();
+
+        for nonce in [0, 1, 2, 4] {
+            assert_eq!(nonces.consume(nonce).unwrap_err(), OmniVaultError::InvalidNonce.into());
+        }
+        nonces.consume(3).unwrap();
+        assert_eq!(nonces.last_nonce, 3);
+    }
+
+    #[test]
+    fn unordered_nonces_accept_each_nonce_in_the_window_once() {
+        let mut nonces = inbound_nonce(NonceOrdering::Unordered);
+        nonces.consume(5).unwrap();
+        nonces.consume(3).unwrap();
+        nonces.consume(7).unwrap();
+
+        for nonce in [3, 5, 7] {
+            assert_eq!(nonces.consume(nonce).unwrap_err(), OmniVaultError::InvalidNonce.into());
+        }
+        nonces.consume(6).unwrap();
+        assert_eq!((nonces.last_nonce, nonces.used_window), (7, 0b10111));
+    }
+
+    #[test]
+    fn unordered_nonces_below_the_window_are_rejected() {
+        let mut nonces = inbound_nonce(NonceOrdering::Unordered);
+        nonces.consume(100).unwrap();
+
+        nonces.consume(100 - (INBOUND_NONCE_WINDOW - 1)).unwrap();
+        assert_eq!(
+            nonces.consume(100 - INBOUND_NONCE_WINDOW).unwrap_err(),
+            OmniVaultError::InvalidNonce.into()
+        );
+    }
+
+    #[test]
+    fn unordered_jump_past_the_window_resets_it() {
+        let mut nonces = inbound_nonce(NonceOrdering::Unordered);
+        nonces.consume(1).unwrap();
+        nonces.consume(2).unwrap();
+
+        nonces.consume(2 + INBOUND_NONCE_WINDOW).unwrap();
+        assert_eq!((nonces.last_nonce, nonces.used_window), (2 + INBOUND_NONCE_WINDOW, 1));
+        nonces.consume(3 + INBOUND_NONCE_WINDOW).unwrap();
+        assert_eq!(nonces.used_window, 0b11);
+        // Older nonces that fell out of the window stay rejected
+        assert_eq!(nonces.consume(2).unwrap_err(), OmniVaultError::InvalidNonce.into());
+    }
+
+    #[test]
+    fn reads_native_fee_from_endpoint_return_data() {
+        let endpoint_id = Pubkey::from_str(LAYERZERO_ENDPOINT).unwrap();
+        let fee = MessagingFee { native_fee: 42_000, lz_token_fee: 7 }.try_to_vec().unwrap();
+
+        assert_eq!(read_quote_fee(Some((endpoint_id, fee.clone())), &endpoint_id).unwrap(), 42_000);
+        for return_data in [None, Some((crate::ID, fee)), Some((endpoint_id, vec![1, 2, 3]))] {
+            assert_eq!(
+                read_quote_fee(return_data, &endpoint_id).unwrap_err(),
+                OmniVaultError::InvalidFeeQuote.into()
+            );
+        }
+    }
+
+    #[test]
+    fn encodes_quote_params_for_the_endpoint() {
+        let params = LzQuoteParams {
+            sender: Pubkey::new_from_array([1; 32]),
+            dst_eid: 30_101,
+            receiver: [2; 32],
+            message: vec![3],
+            options: vec![4, 5],
+            pay_in_lz_token: false,
+        };
+        let mut expected = vec![1; 32];
+        expected.extend_from_slice(&30_101u32.to_le_bytes());
+        expected.extend_from_slice(&[2; 32]);
+        expected.extend_from_slice(&[1, 0, 0, 0, 3, 2, 0, 0, 0, 4, 5, 0]);
+        assert_eq!(params.try_to_vec().unwrap(), expected);
+    }
+
+    #[test]
+    fn caps_the_native_fee_actually_spent() {
+        assert_eq!(native_fee_spent(10_000, 9_000, 1_000).unwrap(), 1_000);
+        assert_eq!(native_fee_spent(10_000, 10_500, 1_000).unwrap(), 0);
+        assert_eq!(
+            native_fee_spent(10_000, 8_999, 1_000).unwrap_err(),
+            OmniVaultError::MessagingFeeTooHigh.into()
+        );
+    }
+
+    // Instructions the program would have invoked, recorded by TestSyscalls
+    static INVOKED: std::sync::Mutex<Vec<Instruction>> = std::sync::Mutex::new(Vec::new());
+
+    // Records CPIs and serves the default Rent sysvar
+    struct TestSyscalls;
+
+    impl anchor_lang::solana_program::program_stubs::SyscallStubs for TestSyscalls {
+        fn sol_get_rent_sysvar(&self, _var_addr: *mut u8) -> u64 {
+            anchor_lang::solana_program::entrypoint::SUCCESS // Leaves Rent::default() in place
+        }
+
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            _account_infos: &[AccountInfo],
+            _signers_seeds: &[&[&[u8]]],
+        ) -> anchor_lang::solana_program::entrypoint::ProgramResult {
+            INVOKED.lock().unwrap().push(instruction.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sends_quoted_params_signed_by_the_oapp() {
+        anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(TestSyscalls));
+        let endpoint_id = Pubkey::from_str(LAYERZERO_ENDPOINT).unwrap();
+        let (store_key, payer_key, nonce_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut store_data = Vec::new();
+        test_store().try_serialize(&mut store_data).unwrap();
+        let (mut store_lamports, mut payer_lamports, mut nonce_lamports, mut endpoint_lamports) = (0, 0, 0, 0);
+        let (mut payer_data, mut nonce_data, mut endpoint_data) = ([0u8; 0], [0u8; 0], [0u8; 0]);
+        let store_info = AccountInfo::new(&store_key, false, false, &mut store_lamports, &mut store_data, &crate::ID, false, 0);
+        let payer_info = AccountInfo::new(&payer_key, true, true, &mut payer_lamports, &mut payer_data, &endpoint_id, false, 0);
+        let nonce_info = AccountInfo::new(&nonce_key, false, true, &mut nonce_lamports, &mut nonce_data, &endpoint_id, false, 0);
+        let endpoint_info = AccountInfo::new(&endpoint_id, false, false, &mut endpoint_lamports, &mut endpoint_data, &endpoint_id, true, 0);
+        let vault_store = Account::<VaultStore>::try_from(&store_info).unwrap();
+        let send_accounts = [store_info.clone(), payer_info, nonce_info];
+        let mut chain_send = ChainSend {
+            chain_id: 101,
+            eid: 30_101,
+            options: vec![0, 3],
+            receiver: [9; 32],
+            quote_accounts: &[],
+            send_accounts: &send_accounts,
+        };
+
+        INVOKED.lock().unwrap().clear();
+        send_lz_message(&endpoint_info, &vault_store, &chain_send, &[7], 5_000).unwrap();
+        let invoked = INVOKED.lock().unwrap().pop().unwrap();
+        assert_eq!(invoked.program_id, endpoint_id);
+        let mut expected = hash(b"global:send").to_bytes()[..8].to_vec();
+        LzSendParams {
+            dst_eid: 30_101,
+            receiver: [9; 32],
+            message: vec![7],
+            options: vec![0, 3],
+            native_fee: 5_000,
+            lz_token_fee: 0,
+        }
+        .serialize(&mut expected)
+        .unwrap();
+        assert_eq!(invoked.data, expected);
+        let signers: Vec<_> = invoked.accounts.iter().map(|meta| (meta.pubkey, meta.is_signer)).collect();
+        assert_eq!(signers, vec![(store_key, true), (payer_key, true), (nonce_key, false)]);
+
+        // Sends must start with the OApp account
+        chain_send.send_accounts = &send_accounts[1..];
+        assert_eq!(
+            send_lz_message(&endpoint_info, &vault_store, &chain_send, &[7], 5_000).unwrap_err(),
+            OmniVaultError::MissingChainConfig.into()
+        );
+    }
+
+    #[test]
+    fn burns_cctp_withdrawals_signed_by_the_vault() {
+        anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(TestSyscalls));
+        let (messenger_key, vault_key, burn_key, event_key) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut messenger_lamports, mut vault_lamports, mut burn_lamports, mut event_lamports) = (0, 0, 0, 0);
+        let (mut messenger_data, mut vault_data, mut burn_data, mut event_data) = ([0u8; 0], [0u8; 0], [0u8; 0], [0u8; 0]);
+        let messenger_info = AccountInfo::new(&messenger_key, false, false, &mut messenger_lamports, &mut messenger_data, &messenger_key, true, 0);
+        let vault_info = AccountInfo::new(&vault_key, false, true, &mut vault_lamports, &mut vault_data, &crate::ID, false, 0);
+        let burn_info = AccountInfo::new(&burn_key, false, true, &mut burn_lamports, &mut burn_data, &messenger_key, false, 0);
+        let event_info = AccountInfo::new(&event_key, true, true, &mut event_lamports, &mut event_data, &messenger_key, false, 0);
+        let recipient = Pubkey::new_unique();
+
+        INVOKED.lock().unwrap().clear();
+        cctp_deposit_for_burn(
+            &messenger_info,
+            &vault_info,
+            &[burn_info, event_info],
+            DepositForBurnParams {
+                amount: 990,
+                destination_domain: 3,
+                mint_recipient: recipient,
+            },
+            &[],
+        )
+        .unwrap();
+        let invoked = INVOKED.lock().unwrap().pop().unwrap();
+        assert_eq!(invoked.program_id, messenger_key);
+        let mut expected = hash(b"global:deposit_for_burn").to_bytes()[..8].to_vec();
+        expected.extend_from_slice(&990u64.to_le_bytes());
+        expected.extend_from_slice(&3u32.to_le_bytes());
+        expected.extend_from_slice(recipient.as_ref());
+        assert_eq!(invoked.data, expected);
+        let metas: Vec<_> = invoked
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_signer, meta.is_writable))
+            .collect();
+        assert_eq!(metas, vec![(vault_key, true, false), (burn_key, false, true), (event_key, true, true)]);
+    }
+
+    #[test]
+    fn sol_liquidity_keeps_rent_fees_and_emergency_reserve() {
+        anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(TestSyscalls));
+        let key = Pubkey::new_unique();
+        let mut data = [0u8; 8 + Vault::INIT_SPACE];
+        let rent_floor = Rent::default().minimum_balance(data.len());
+        let mut vault = test_vault();
+        vault.accrued_fees = 30;
+        vault.emergency_reserved = 20;
+
+        let mut lamports = rent_floor + 1_000;
+        let vault_info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        assert_eq!(sol_liquidity(&vault_info, &vault).unwrap(), 950);
+
+        // Fee and reserve lamports below the rent floor never become liquidity
+        let mut lamports = rent_floor + 40;
+        let vault_info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        assert_eq!(sol_liquidity(&vault_info, &vault).unwrap(), 0);
+    }
+
+    #[test]
+    fn forwards_transfer_hook_accounts_to_token_2022() {
+        use anchor_spl::token_2022::spl_token_2022::{
+            extension::{
+                transfer_hook::TransferHook, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+            },
+            state::Mint as MintState,
+        };
+        use spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList};
+        use spl_transfer_hook_interface::{get_extra_account_metas_address, instruction::ExecuteInstruction};
+
+        let token_program_id = anchor_spl::token_2022::ID;
+        let hook_program_id = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let source_key = Pubkey::new_unique();
+        let destination_key = Pubkey::new_unique();
+        let authority_key = Pubkey::new_unique();
+        let extra_key = Pubkey::new_unique();
+        let validation_key = get_extra_account_metas_address(&mint_key, &hook_program_id);
+
+        // Mint whose transfers must go through the hook program
+        let mint_len = ExtensionType::try_calculate_account_len::<MintState>(&[ExtensionType::TransferHook]).unwrap();
+        let mut mint_data = vec![0; mint_len];
+        let mut mint_state = StateWithExtensionsMut::<MintState>::unpack_uninitialized(&mut mint_data).unwrap();
+        mint_state.init_extension::<TransferHook>(true).unwrap().program_id =
+            spl_pod::optional_keys::OptionalNonZeroPubkey(hook_program_id);
+        mint_state.base = MintState { decimals: 6, is_initialized: true, ..Default::default() };
+        mint_state.pack_base();
+        mint_state.init_account_type().unwrap();
+
+        // The hook asks for one extra writable account on every transfer
+        let extra_metas = [ExtraAccountMeta::new_with_pubkey(&extra_key, false, true).unwrap()];
+        let mut validation_data = vec![0; ExtraAccountMetaList::size_of(extra_metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut validation_data, &extra_metas).unwrap();
+
+        let mut lamports = [0u64; 8];
+        let [l0, l1, l2, l3, l4, l5, l6, l7] = &mut lamports;
+        let (mut d0, mut d1, mut d2, mut d3, mut d4) = ([0u8; 0], [0u8; 0], [0u8; 0], [0u8; 0], [0u8; 0]);
+        let token_program_info = AccountInfo::new(&token_program_id, false, false, l0, &mut d0, &token_program_id, true, 0);
+        let mint_info = AccountInfo::new(&mint_key, false, false, l1, &mut mint_data, &token_program_id, false, 0);
+        let source = AccountInfo::new(&source_key, false, true, l2, &mut d1, &token_program_id, false, 0);
+        let destination = AccountInfo::new(&destination_key, false, true, l3, &mut d2, &token_program_id, false, 0);
+        let authority = AccountInfo::new(&authority_key, true, false, l4, &mut d3, &system_program::ID, false, 0);
+        let extra = AccountInfo::new(&extra_key, false, true, l5, &mut d4, &hook_program_id, false, 0);
+        let hook_program = AccountInfo::new(&hook_program_id, false, false, l6, &mut [], &hook_program_id, true, 0);
+        let validation = AccountInfo::new(&validation_key, false, false, l7, &mut validation_data, &hook_program_id, false, 0);
+
+        let token_program = Interface::<TokenInterface>::try_from(&token_program_info).unwrap();
+        let mint = InterfaceAccount::<Mint>::try_from(&mint_info).unwrap();
+        anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(TestSyscalls));
+        let remaining_accounts = [extra, hook_program, validation];
+        transfer_tokens(&token_program, source, &mint, destination, authority, &remaining_accounts, 1_000, &[]).unwrap();
+
+        // The remaining accounts reach Token-2022 as the hook's extra accounts
+        let invoked = INVOKED.lock().unwrap().pop().unwrap();
+        assert_eq!(invoked.program_id, token_program_id);
+        let keys: Vec<Pubkey> = invoked.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(
+            keys,
+            [source_key, mint_key, destination_key, authority_key, extra_key, validation_key, hook_program_id]
+        );
+        assert!(invoked.accounts[4].is_writable);
+    }
+
+    #[test]
+    fn encodes_lz_receive_gas() {
+        // OptionsBuilder.newOptions().addExecutorLzReceiveOption(200000, 0)
+        assert_eq!(
+            hex(&lz_receive_only(200_000).encode()),
+            "00030100110100000000000000000000000000030d40"
+        );
+        // OptionsBuilder.newOptions().addExecutorLzReceiveOption(65000, 0)
+        assert_eq!(
+            hex(&lz_receive_only(65_000).encode()),
+            "0003010011010000000000000000000000000000fde8"
+        );
+    }
+
+    #[test]
+    fn encodes_lz_receive_value() {
+        let options = ExecutorOptions {
+            lz_receive_value: 1_000_000_000_000_000,
+            ..lz_receive_only(200_000)
+        };
+        assert_eq!(
+            hex(&options.encode()),
+            "00030100210100000000000000000000000000030d40000000000000000000038d7ea4c68000"
+        );
+    }
+
+    #[test]
+    fn encodes_native_drop() {
+        let mut receiver = [0u8; 32];
+        for (i, byte) in receiver.iter_mut().enumerate() {
+            *byte = i as u8 + 1;
+        }
+        let options = ExecutorOptions {
+            native_drop_amount: 1_000_000,
+            native_drop_receiver: receiver,
+            ..lz_receive_only(200_000)
+        };
+        assert_eq!(
+            hex(&options.encode()),
+            "00030100110100000000000000000000000000030d40\
+             01003102000000000000000000000000000f4240\
+             0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20"
+        );
+    }
+
+    #[test]
+    fn encodes_lz_compose_gas() {
+        let options = ExecutorOptions {
+            compose_gas: 500_000,
+            ..lz_receive_only(200_000)
+        };
+        assert_eq!(
+            hex(&options.encode()),
+            "00030100110100000000000000000000000000030d40\
+             0100130300000000000000000000000000000007a120"
+        );
+    }
+
+    #[test]
+    fn closes_only_the_vaults_inbound_nonces() {
+        let vault = Pubkey::new_unique();
+        let nonce_data = |vault: Pubkey, src_chain_id: u16| {
+            let (key, bump) = Pubkey::find_program_address(
+                &[b"inbound_nonce", vault.as_ref(), &src_chain_id.to_le_bytes()],
+                &crate::ID,
+            );
+            let nonce = InboundNonce {
+                vault,
+                src_chain_id,
+                ordering: NonceOrdering::Unordered,
+                last_nonce: 7,
+                used_window: 1,
+                bump,
+            };
+            // Leave room for the length prefix the runtime writes ahead of account data on resize
+            let mut data = vec![0u8; 8];
+            nonce.try_serialize(&mut data).unwrap();
+            (key, data)
+        };
+        // assign() writes through the owner reference, so each account gets its own copy of the id
+        let (nonce_owner, other_owner) = (crate::ID, crate::ID);
+
+        let (nonce_key, mut data) = nonce_data(vault, 30101);
+        let extra_key = Pubkey::new_unique();
+        let receiver_key = Pubkey::new_unique();
+        let (mut nonce_lamports, mut extra_lamports, mut receiver_lamports) = (1_000, 5, 10);
+        let mut extra_data = [0u8; 0];
+        let mut receiver_data = [0u8; 0];
+        let nonce_info = AccountInfo::new(&nonce_key, false, true, &mut nonce_lamports, &mut data[8..], &nonce_owner, false, 0);
+        let extra_info = AccountInfo::new(&extra_key, false, false, &mut extra_lamports, &mut extra_data, &extra_key, false, 0);
+        let receiver_info = AccountInfo::new(&receiver_key, true, true, &mut receiver_lamports, &mut receiver_data, &system_program::ID, false, 0);
+
+        let rest = close_inbound_nonces(vault, &[nonce_info.clone(), extra_info], receiver_info.clone()).unwrap();
+        assert_eq!(rest.iter().map(|account| account.key()).collect::<Vec<_>>(), vec![extra_key]);
+        assert_eq!((nonce_info.lamports(), receiver_info.lamports()), (0, 1_010));
+        assert_eq!(nonce_info.owner, &system_program::ID);
+        assert!(nonce_info.data_is_empty());
+
+        // Another vault's nonce is rejected rather than closed or forwarded
+        let (other_key, mut other_data) = nonce_data(Pubkey::new_unique(), 30101);
+        let mut other_lamports = 1_000;
+        let other_info = AccountInfo::new(&other_key, false, true, &mut other_lamports, &mut other_data[8..], &other_owner, false, 0);
+        assert_eq!(
+            close_inbound_nonces(vault, &[other_info], receiver_info).unwrap_err(),
+            OmniVaultError::InvalidVaultId.into()
+        );
+    }
+
+    #[test]
+    fn rejects_lz_receive_as_a_top_level_instruction() {
+        use solana_instruction::{BorrowedAccountMeta, BorrowedInstruction};
+        use anchor_lang::solana_program::sysvar::instructions::{construct_instructions_data, store_current_index};
+
+        let sysvar_key = anchor_lang::solana_program::sysvar::instructions::ID;
+        let executor = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let check = |program_id: &Pubkey| {
+            let meta = BorrowedAccountMeta { pubkey: &payer, is_signer: true, is_writable: true };
+            let instruction = BorrowedInstruction { program_id, accounts: vec![meta], data: &[] };
+            let mut data = construct_instructions_data(&[instruction]);
+            store_current_index(&mut data, 0);
+            let mut lamports = 0;
+            let sysvar_owner = anchor_lang::solana_program::sysvar::ID;
+            let info = AccountInfo::new(&sysvar_key, false, false, &mut lamports, &mut data, &sysvar_owner, false, 0);
+            require_invoked_by_cpi(&info)
+        };
+
+        assert!(check(&executor).is_ok());
+        assert_eq!(check(&crate::ID).unwrap_err(), OmniVaultError::LzReceiveNotInvokedByCpi.into());
+    }
 }
 
@@ -1,8 +1,163 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Omnivault } from "../target/types/omnivault";
-import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
-import { expect } from "chai";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import { AssertionError, expect } from "chai";
+
+const TOKEN_PROGRAM_ID = anchor.utils.token.TOKEN_PROGRAM_ID;
+const TOKEN_2022_PROGRAM_ID = new PublicKey("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+const ASSOCIATED_TOKEN_PROGRAM_ID = anchor.utils.token.ASSOCIATED_PROGRAM_ID;
+// Wrapped SOL mint, available on every cluster including the local validator
+const NATIVE_MINT = new PublicKey("So11111111111111111111111111111111111111112");
+const LAYERZERO_ENDPOINT = new PublicKey("LZ1ZeTMZZnKWEcG2ukQpvJE2QnLEyV5uYPVfPjTvZmV");
+// CCTPConfig preloaded into the test validator from tests/fixtures/cctp_config.json
+const CCTP_CONFIG_FIXTURE = new PublicKey("AxyFsz65PXCwt1yufiYFbB1G9627gNTDt8yr19q229sf");
+
+// Minimal SPL token helpers built from raw instructions
+function associatedTokenAddress(
+  owner: PublicKey,
+  mint: PublicKey,
+  tokenProgram: PublicKey = TOKEN_PROGRAM_ID
+): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [owner.toBuffer(), tokenProgram.toBuffer(), mint.toBuffer()],
+    ASSOCIATED_TOKEN_PROGRAM_ID
+  )[0];
+}
+
+async function createAssociatedTokenAccount(
+  provider: anchor.AnchorProvider,
+  owner: PublicKey,
+  mint: PublicKey,
+  tokenProgram: PublicKey = TOKEN_PROGRAM_ID
+): Promise<PublicKey> {
+  const address = associatedTokenAddress(owner, mint, tokenProgram);
+  const ix = new TransactionInstruction({
+    programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+    keys: [
+      { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },
+      { pubkey: address, isSigner: false, isWritable: true },
+      { pubkey: owner, isSigner: false, isWritable: false },
+      { pubkey: mint, isSigner: false, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: tokenProgram, isSigner: false, isWritable: false },
+    ],
+    data: Buffer.from([1]), // CreateIdempotent
+  });
+  await provider.sendAndConfirm(new Transaction().add(ix));
+  return address;
+}
+
+function syncNativeInstruction(account: PublicKey): TransactionInstruction {
+  return new TransactionInstruction({
+    programId: TOKEN_PROGRAM_ID,
+    keys: [{ pubkey: account, isSigner: false, isWritable: true }],
+    data: Buffer.from([17]), // SyncNative
+  });
+}
+
+// Fund a token account of the native mint with lamports and sync its balance
+async function wrapSol(
+  provider: anchor.AnchorProvider,
+  account: PublicKey,
+  lamports: number
+): Promise<void> {
+  await provider.sendAndConfirm(
+    new Transaction().add(
+      SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: account,
+        lamports,
+      }),
+      syncNativeInstruction(account)
+    )
+  );
+}
+
+async function tokenBalance(provider: anchor.AnchorProvider, account: PublicKey): Promise<anchor.BN> {
+  const balance = await provider.connection.getTokenAccountBalance(account);
+  return new anchor.BN(balance.value.amount);
+}
+
+// Create a Token-2022 mint that withholds `feeBasisPoints` of every transfer, up to `maximumFee`
+async function createTransferFeeMint(
+  provider: anchor.AnchorProvider,
+  mintAuthority: PublicKey,
+  decimals: number,
+  feeBasisPoints: number,
+  maximumFee: bigint
+): Promise<PublicKey> {
+  const mint = Keypair.generate();
+  const space = 278; // Base mint padded to 165 bytes, account type and the TransferFeeConfig entry
+  const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+
+  const feeConfigData = Buffer.alloc(14);
+  feeConfigData.writeUInt8(26, 0); // TransferFeeExtension
+  feeConfigData.writeUInt8(0, 1); // InitializeTransferFeeConfig
+  feeConfigData.writeUInt8(0, 2); // No fee config authority
+  feeConfigData.writeUInt8(0, 3); // No withdraw withheld authority
+  feeConfigData.writeUInt16LE(feeBasisPoints, 4);
+  feeConfigData.writeBigUInt64LE(maximumFee, 6);
+
+  const mintData = Buffer.concat([
+    Buffer.from([20, decimals]), // InitializeMint2
+    mintAuthority.toBuffer(),
+    Buffer.from([0]), // No freeze authority
+  ]);
+
+  await provider.sendAndConfirm(
+    new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: provider.wallet.publicKey,
+        newAccountPubkey: mint.publicKey,
+        space,
+        lamports,
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      new TransactionInstruction({
+        programId: TOKEN_2022_PROGRAM_ID,
+        keys: [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+        data: feeConfigData,
+      }),
+      new TransactionInstruction({
+        programId: TOKEN_2022_PROGRAM_ID,
+        keys: [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+        data: mintData,
+      })
+    ),
+    [mint]
+  );
+  return mint.publicKey;
+}
+
+async function mintTokens(
+  provider: anchor.AnchorProvider,
+  mint: PublicKey,
+  destination: PublicKey,
+  mintAuthority: Keypair,
+  amount: bigint,
+  tokenProgram: PublicKey = TOKEN_PROGRAM_ID
+): Promise<void> {
+  const data = Buffer.alloc(9);
+  data.writeUInt8(7, 0); // MintTo
+  data.writeBigUInt64LE(amount, 1);
+  const ix = new TransactionInstruction({
+    programId: tokenProgram,
+    keys: [
+      { pubkey: mint, isSigner: false, isWritable: true },
+      { pubkey: destination, isSigner: false, isWritable: true },
+      { pubkey: mintAuthority.publicKey, isSigner: true, isWritable: false },
+    ],
+    data,
+  });
+  await provider.sendAndConfirm(new Transaction().add(ix), [mintAuthority]);
+}
 
 describe("OmniVault", () => {
   // Configure the client to use the local cluster
@@ -13,6 +168,7 @@ describe("OmniVault", () => {
   
   // Test accounts
   let vaultStore: PublicKey;
+  let roles: PublicKey;
   let authority: Keypair;
   let vaultOwner: Keypair;
 
@@ -31,8 +187,69 @@ describe("OmniVault", () => {
       [Buffer.from("vault_store")],
       program.programId
     );
+
+    // Find PDA for the role registry
+    [roles] = PublicKey.findProgramAddressSync(
+      [Buffer.from("roles"), vaultStore.toBuffer()],
+      program.programId
+    );
   });
 
+  // The vault created by the most recent "Creates a new vault" run
+  async function latestVault(): Promise<PublicKey> {
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const latestVaultId = vaultStoreAccount.totalVaults.toNumber() - 1;
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        new anchor.BN(latestVaultId).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+  }
+
+  async function newFundedUser(lamports: number = anchor.web3.LAMPORTS_PER_SOL): Promise<Keypair> {
+    const user = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(user.publicKey, lamports)
+    );
+    return user;
+  }
+
+  // Wrap SOL for the user and deposit it into a wrapped SOL vault
+  async function depositWrappedSol(vault: PublicKey, user: Keypair, lamports: number) {
+    const vaultAccount = await program.account.vault.fetch(vault);
+    const userTokenAccount = await createAssociatedTokenAccount(provider, user.publicKey, NATIVE_MINT);
+    await wrapSol(provider, userTokenAccount, lamports);
+    const [userPosition] = PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), vault.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+    const userShareAccount = associatedTokenAddress(user.publicKey, vaultAccount.shareMint);
+
+    await program.methods
+      .deposit(new anchor.BN(lamports))
+      .accounts({
+        vault,
+        userPosition,
+        vaultStore,
+        user: user.publicKey,
+        userTokenAccount,
+        vaultTokenAccount: vaultAccount.tokenAccount,
+        shareMint: vaultAccount.shareMint,
+        userShareAccount,
+        assetMint: NATIVE_MINT,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    return { userTokenAccount, userShareAccount, userPosition };
+  }
+
   it("Initializes the vault store", async () => {
     try {
       // Try to fetch existing vault store first
@@ -63,9 +280,40 @@ describe("OmniVault", () => {
     expect(vaultStoreAccount.totalVaults.toNumber()).to.be.at.least(0);
     expect(vaultStoreAccount.feeRate).to.equal(100); // 1%
     expect(vaultStoreAccount.emergencyPause).to.equal(false);
+    expect(vaultStoreAccount.version).to.equal(1);
     console.log("✅ Vault store initialized successfully");
   });
 
+  it("Initializes the role registry", async () => {
+    const existingRoles = await program.account.roles.fetchNullable(roles);
+    if (existingRoles) {
+      console.log("Role registry already exists, skipping initialization");
+      return;
+    }
+
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    if (!vaultStoreAccount.authority.equals(authority.publicKey)) {
+      console.log("⚠️  Authority mismatch - skipping role registry initialization");
+      return;
+    }
+
+    await program.methods
+      .initializeRoles()
+      .accounts({
+        roles,
+        vaultStore,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const rolesAccount = await program.account.roles.fetch(roles);
+    expect(rolesAccount.vaultStore.toString()).to.equal(vaultStore.toString());
+    expect(rolesAccount.keepers).to.be.empty;
+    console.log("✅ Role registry initialized successfully");
+  });
+
   it("Creates a new vault", async () => {
     // Get current vault count to determine next vault ID
     const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
@@ -87,17 +335,34 @@ describe("OmniVault", () => {
       program.programId
     );
 
+    // Find PDA for the vault share mint
+    const [shareMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vault.toBuffer()],
+      program.programId
+    );
+
+    // Find PDA for the vault's asset token account
+    const [vaultTokenAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_tokens"), vault.toBuffer()],
+      program.programId
+    );
+
     const riskProfile = { conservative: {} };
     const minDeposit = new anchor.BN(1 * 10**9); // 1 token
     const targetChains = [101, 110]; // Ethereum and Arbitrum
+    const depositCap = new anchor.BN(1_000_000 * 10**9); // 1M tokens
 
     const tx = await program.methods
-      .createVault(riskProfile, minDeposit, targetChains)
+      .createVault(riskProfile, minDeposit, targetChains, depositCap, { spl: {} })
       .accounts({
         vault,
         yieldTracker,
+        shareMint,
+        assetMint: NATIVE_MINT,
+        vaultTokenAccount,
         vaultStore,
         owner: vaultOwner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .signers([vaultOwner])
@@ -114,12 +379,27 @@ describe("OmniVault", () => {
     expect(vaultAccount.minDeposit.toNumber()).to.equal(minDeposit.toNumber());
     expect(vaultAccount.isActive).to.equal(true);
     expect(vaultAccount.targetChains).to.deep.equal(targetChains);
+    expect(vaultAccount.shareMint.toString()).to.equal(shareMint.toString());
+    expect(vaultAccount.totalYield.toNumber()).to.equal(0);
+    expect(vaultAccount.sharePrice.toNumber()).to.equal(1_000_000_000); // 1.0
+    expect(vaultAccount.depositCap.toString()).to.equal(depositCap.toString());
+    expect(vaultAccount.assetMint.toString()).to.equal(NATIVE_MINT.toString());
+    expect(vaultAccount.assetKind).to.deep.equal({ spl: {} });
+    expect(vaultAccount.tokenAccount.toString()).to.equal(vaultTokenAccount.toString());
+    expect(vaultAccount.version).to.equal(1);
+
+    // Verify the share mint is controlled by the vault
+    const shareMintInfo = await provider.connection.getParsedAccountInfo(shareMint);
+    const parsedMint = (shareMintInfo.value?.data as any).parsed.info;
+    expect(parsedMint.mintAuthority).to.equal(vault.toString());
+    expect(parsedMint.supply).to.equal("0");
 
     // Verify yield tracker was created
     const yieldTrackerAccount = await program.account.yieldTracker.fetch(yieldTracker);
     expect(yieldTrackerAccount.vault.toString()).to.equal(vault.toString());
     expect(yieldTrackerAccount.chainYields).to.be.empty;
     expect(yieldTrackerAccount.queryNonce.toNumber()).to.equal(0);
+    expect(yieldTrackerAccount.version).to.equal(1);
 
     // Verify vault store was updated
     const updatedVaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
@@ -128,7 +408,7 @@ describe("OmniVault", () => {
     console.log("✅ Vault created successfully");
   });
 
-  it("Updates vault configuration", async () => {
+  it("Queues a vault configuration change behind the timelock", async () => {
     // Get the vault we created (should be vault ID 0 or the latest one)
     const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
     const latestVaultId = vaultStoreAccount.totalVaults.toNumber() - 1;
@@ -142,36 +422,181 @@ describe("OmniVault", () => {
       ],
       program.programId
     );
+    const [shareMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vault.toBuffer()],
+      program.programId
+    );
+    const [pendingConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pending_config"), vault.toBuffer()],
+      program.programId
+    );
 
     const newMinDeposit = new anchor.BN(2 * 10**9); // 2 tokens
-    const newActiveStatus = true;
     const newRebalanceThreshold = new anchor.BN(200); // 2%
     const newTargetChains = [101, 110, 109]; // Add Polygon
+    const lockPeriod = new anchor.BN(7 * 24 * 3600); // 1 week
+
+    // Early-exit penalties are bounded at queue time
+    try {
+      await program.methods
+        .queueVaultConfig(null, null, null, null, null, null, lockPeriod, 5000)
+        .accounts({
+          pendingConfig,
+          vault,
+          vaultStore,
+          owner: vaultOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([vaultOwner])
+        .rpc();
+      expect.fail("A 50% early-exit penalty should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("FeeTooHigh");
+    }
 
     const tx = await program.methods
-      .updateVaultConfig(
+      .queueVaultConfig(
         newMinDeposit,
-        newActiveStatus,
         newRebalanceThreshold,
-        newTargetChains
+        newTargetChains,
+        null,
+        null,
+        null,
+        lockPeriod,
+        200 // 2% penalty decaying over the lock
       )
       .accounts({
+        pendingConfig,
         vault,
+        vaultStore,
         owner: vaultOwner.publicKey,
+        systemProgram: SystemProgram.programId,
       })
       .signers([vaultOwner])
       .rpc();
 
-    console.log("Update vault config transaction signature:", tx);
+    console.log("Queue vault config transaction signature:", tx);
 
-    // Verify vault configuration was updated
-    const vaultAccount = await program.account.vault.fetch(vault);
-    expect(vaultAccount.minDeposit.toNumber()).to.equal(newMinDeposit.toNumber());
-    expect(vaultAccount.isActive).to.equal(newActiveStatus);
-    expect(vaultAccount.rebalanceThreshold.toNumber()).to.equal(newRebalanceThreshold.toNumber());
-    expect(vaultAccount.targetChains).to.deep.equal(newTargetChains);
+    // The change is stored with an ETA and not applied yet
+    const pendingConfigAccount = await program.account.pendingConfig.fetch(pendingConfig);
+    expect(pendingConfigAccount.target.toString()).to.equal(vault.toString());
+    expect(pendingConfigAccount.eta.toNumber()).to.equal(
+      pendingConfigAccount.queuedAt.toNumber() + vaultStoreAccount.configDelay.toNumber()
+    );
+    let vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.targetChains).to.not.deep.equal(newTargetChains);
+
+    // Executing before the ETA fails
+    try {
+      await program.methods
+        .executeVaultConfig()
+        .accounts({
+          pendingConfig,
+          vault,
+          shareMint,
+          vaultStore,
+          proposer: vaultOwner.publicKey,
+          executor: vaultOwner.publicKey,
+        })
+        .signers([vaultOwner])
+        .rpc();
+      expect.fail("Execution before the timelock expires should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("TimelockNotExpired");
+    }
+
+    // The owner can withdraw the queued change
+    await program.methods
+      .cancelConfig()
+      .accounts({
+        pendingConfig,
+        vaultStore,
+        roles,
+        proposer: vaultOwner.publicKey,
+        canceller: vaultOwner.publicKey,
+      })
+      .signers([vaultOwner])
+      .rpc();
+
+    expect(await program.account.pendingConfig.fetchNullable(pendingConfig)).to.be.null;
+
+    // Activation status stays instant
+    await program.methods
+      .setVaultActive(true)
+      .accounts({
+        vault,
+        owner: vaultOwner.publicKey,
+      })
+      .signers([vaultOwner])
+      .rpc();
+
+    vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.isActive).to.equal(true);
     
-    console.log("✅ Vault configuration updated successfully");
+    console.log("✅ Vault configuration change queued and cancelled successfully");
+  });
+
+  it("Queues a store configuration change behind the timelock", async () => {
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    if (!vaultStoreAccount.authority.equals(authority.publicKey)) {
+      console.log("⚠️  Authority mismatch - skipping store configuration test");
+      return;
+    }
+
+    const [pendingConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pending_config"), vaultStore.toBuffer()],
+      program.programId
+    );
+
+    // Fee rate above the hard cap is rejected at queue time
+    try {
+      await program.methods
+        .queueStoreConfig(5000, [], [], null, null)
+        .accounts({
+          pendingConfig,
+          vaultStore,
+          authority: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+      expect.fail("Fee rate above the cap should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("FeeTooHigh");
+    }
+
+    const BASE = 184;
+    await program.methods
+      .queueStoreConfig(null, [BASE], [], null, null)
+      .accounts({
+        pendingConfig,
+        vaultStore,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const pendingConfigAccount = await program.account.pendingConfig.fetch(pendingConfig);
+    expect(pendingConfigAccount.target.toString()).to.equal(vaultStore.toString());
+    expect(pendingConfigAccount.change.store.addChains).to.deep.equal([BASE]);
+
+    // The store authority can cancel the queued change
+    await program.methods
+      .cancelConfig()
+      .accounts({
+        pendingConfig,
+        vaultStore,
+        roles,
+        proposer: authority.publicKey,
+        canceller: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc();
+
+    const updatedVaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    expect(updatedVaultStoreAccount.supportedChains).to.not.include(BASE);
+    console.log("✅ Store configuration change queued and cancelled successfully");
   });
 
   it("Handles emergency pause and resume", async () => {
@@ -194,7 +619,8 @@ describe("OmniVault", () => {
       .emergencyPause()
       .accounts({
         vaultStore,
-        authority: authority.publicKey,
+        roles,
+        guardian: authority.publicKey,
       })
       .signers([authority])
       .rpc();
@@ -223,4 +649,1121 @@ describe("OmniVault", () => {
     expect(updatedVaultStoreAccount.emergencyPause).to.equal(false);
     console.log("✅ Operations resumed successfully");
   });
+
+  it("Sets store pause flags as guardian and clears them as authority", async () => {
+    const DEPOSITS = 1;
+    const WITHDRAWALS = 2;
+
+    await program.methods
+      .setStorePauseFlags(DEPOSITS | WITHDRAWALS)
+      .accounts({
+        vaultStore,
+        roles,
+        guardian: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc();
+
+    let vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    expect(vaultStoreAccount.pauseFlags).to.equal(DEPOSITS | WITHDRAWALS);
+
+    // Only the store authority can lift a pause
+    const randomSigner = Keypair.generate();
+    try {
+      await program.methods
+        .clearStorePauseFlags(DEPOSITS)
+        .accounts({
+          vaultStore,
+          authority: randomSigner.publicKey,
+        })
+        .signers([randomSigner])
+        .rpc();
+      expect.fail("Clearing pause flags by a random signer should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("ConstraintHasOne");
+    }
+
+    await program.methods
+      .clearStorePauseFlags(DEPOSITS | WITHDRAWALS)
+      .accounts({
+        vaultStore,
+        authority: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc();
+
+    vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    expect(vaultStoreAccount.pauseFlags).to.equal(0);
+    console.log("✅ Store pause flags set and cleared");
+  });
+
+  it("Pays collected fees out of the treasury to the fee manager only", async () => {
+    const [feeTreasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_treasury"), NATIVE_MINT.toBuffer()],
+      program.programId
+    );
+    const [treasuryTokenAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_tokens"), NATIVE_MINT.toBuffer()],
+      program.programId
+    );
+
+    if (!(await program.account.feeTreasury.fetchNullable(feeTreasury))) {
+      await program.methods
+        .initializeFeeTreasury()
+        .accounts({
+          feeTreasury,
+          treasuryTokenAccount,
+          mint: NATIVE_MINT,
+          vaultStore,
+          authority: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    }
+
+    // Stand in for collected fees
+    const amount = new anchor.BN(50_000_000);
+    await wrapSol(provider, treasuryTokenAccount, amount.toNumber());
+
+    const recipient = Keypair.generate();
+    const destination = await createAssociatedTokenAccount(provider, recipient.publicKey, NATIVE_MINT);
+
+    const randomSigner = Keypair.generate();
+    try {
+      await program.methods
+        .withdrawTreasury(amount)
+        .accounts({
+          feeTreasury,
+          treasuryTokenAccount,
+          mint: NATIVE_MINT,
+          destination,
+          vaultStore,
+          roles,
+          feeManager: randomSigner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([randomSigner])
+        .rpc();
+      expect.fail("Treasury withdrawal by a random signer should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("UnauthorizedCaller");
+    }
+
+    const treasuryBefore = await tokenBalance(provider, treasuryTokenAccount);
+    await program.methods
+      .withdrawTreasury(amount)
+      .accounts({
+        feeTreasury,
+        treasuryTokenAccount,
+        mint: NATIVE_MINT,
+        destination,
+        vaultStore,
+        roles,
+        feeManager: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    expect((await tokenBalance(provider, destination)).toString()).to.equal(amount.toString());
+    expect((await tokenBalance(provider, treasuryTokenAccount)).toString()).to.equal(
+      treasuryBefore.sub(amount).toString()
+    );
+    console.log("✅ Treasury withdrawal signed by the fee treasury PDA");
+  });
+
+  it("Queues, fulfills in order, cancels and claims withdrawals", async () => {
+    const vault = await latestVault();
+    let vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.withdrawalQueueHead.toString()).to.equal(vaultAccount.withdrawalQueueTail.toString());
+    const head = vaultAccount.withdrawalQueueHead;
+
+    const rolesAccount = await program.account.roles.fetch(roles);
+    if (!rolesAccount.keepers.some((keeper) => keeper.equals(authority.publicKey))) {
+      await program.methods
+        .grantRole({ keeper: {} }, authority.publicKey)
+        .accounts({ roles, vaultStore, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+    }
+
+    const user = await newFundedUser();
+    const { userTokenAccount, userShareAccount, userPosition } = await depositWrappedSol(
+      vault,
+      user,
+      3 * anchor.web3.LAMPORTS_PER_SOL
+    );
+    const [shareEscrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share_escrow"), vault.toBuffer()],
+      program.programId
+    );
+    const requestAddress = (index: anchor.BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("withdrawal_request"), vault.toBuffer(), index.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    // Two requests take consecutive queue positions
+    const shares = (await tokenBalance(provider, userShareAccount)).divn(3);
+    const requests = [requestAddress(head), requestAddress(head.addn(1))];
+    for (const withdrawalRequest of requests) {
+      await program.methods
+        .requestWithdrawal(shares)
+        .accounts({
+          vault,
+          vaultStore,
+          withdrawalRequest,
+          userPosition,
+          shareMint: vaultAccount.shareMint,
+          userShareAccount,
+          shareEscrow,
+          user: user.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+    }
+    const first = await program.account.withdrawalRequest.fetch(requests[0]);
+    const second = await program.account.withdrawalRequest.fetch(requests[1]);
+    expect(first.index.toString()).to.equal(head.toString());
+    expect(second.index.toString()).to.equal(head.addn(1).toString());
+    expect(second.shares.toString()).to.equal(shares.toString());
+    expect((await tokenBalance(provider, shareEscrow)).toString()).to.equal(shares.muln(2).toString());
+
+    const fulfill = (withdrawalRequest: PublicKey) =>
+      program.methods
+        .fulfillWithdrawal()
+        .accounts({
+          vault,
+          withdrawalRequest,
+          shareMint: vaultAccount.shareMint,
+          shareEscrow,
+          vaultTokenAccount: vaultAccount.tokenAccount,
+          vaultStore,
+          roles,
+          keeper: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+    const claim = (withdrawalRequest: PublicKey) =>
+      program.methods
+        .claimWithdrawal()
+        .accounts({
+          vault,
+          vaultStore,
+          withdrawalRequest,
+          userPosition,
+          user: user.publicKey,
+          userTokenAccount,
+          vaultTokenAccount: vaultAccount.tokenAccount,
+          assetMint: NATIVE_MINT,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+    // The queue is strictly FIFO
+    try {
+      await fulfill(requests[1]);
+      expect.fail("Fulfilling behind the head of the queue should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("WithdrawalOutOfOrder");
+    }
+
+    await fulfill(requests[0]);
+    const fulfilled = await program.account.withdrawalRequest.fetch(requests[0]);
+    expect(fulfilled.status).to.deep.equal({ fulfilled: {} });
+    expect(fulfilled.shares.toNumber()).to.equal(0);
+    expect(fulfilled.amount.toNumber()).to.be.greaterThan(0);
+    expect(fulfilled.claimableAt.sub(fulfilled.requestedAt).toNumber()).to.be.at.least(3600);
+    vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.withdrawalQueueHead.toString()).to.equal(head.addn(1).toString());
+    expect(vaultAccount.reservedWithdrawals.toString()).to.equal(fulfilled.amount.toString());
+
+    // Fulfilled funds wait out the settlement period
+    try {
+      await claim(requests[0]);
+      expect.fail("Claiming inside the settlement period should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("SettlementPending");
+    }
+
+    // Cancelling returns the escrowed shares and moves the head past the request
+    const sharesBefore = await tokenBalance(provider, userShareAccount);
+    await program.methods
+      .cancelWithdrawal()
+      .accounts({
+        vault,
+        withdrawalRequest: requests[1],
+        userPosition,
+        shareMint: vaultAccount.shareMint,
+        shareEscrow,
+        userShareAccount,
+        user: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+    expect((await tokenBalance(provider, userShareAccount)).toString()).to.equal(
+      sharesBefore.add(shares).toString()
+    );
+    vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.withdrawalQueueHead.toString()).to.equal(vaultAccount.withdrawalQueueTail.toString());
+
+    // Claiming the cancelled request pays nothing and closes it
+    await claim(requests[1]);
+    expect(await program.account.withdrawalRequest.fetchNullable(requests[1])).to.be.null;
+    expect(await program.account.withdrawalRequest.fetchNullable(requests[0])).to.not.be.null;
+    console.log("✅ Withdrawal queue processed in order");
+  });
+
+  it("Rejects the vault's primary mint as an additional asset", async () => {
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const latestVaultId = vaultStoreAccount.totalVaults.toNumber() - 1;
+
+    const [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        new anchor.BN(latestVaultId).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    );
+    const [vaultAsset] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_asset"), vault.toBuffer(), NATIVE_MINT.toBuffer()],
+      program.programId
+    );
+    const [assetTokenAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("asset_tokens"), vaultAsset.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .addVaultAsset(new anchor.BN(1), new anchor.BN(0))
+        .accounts({
+          vault,
+          vaultAsset,
+          assetMint: NATIVE_MINT,
+          assetTokenAccount,
+          vaultStore,
+          owner: vaultOwner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([vaultOwner])
+        .rpc();
+      expect.fail("Adding the primary mint should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("AssetAlreadyAdded");
+    }
+
+    const vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.extraAssets).to.deep.equal([]);
+  });
+
+  it("Refuses to close a vault that is still active", async () => {
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const latestVaultId = vaultStoreAccount.totalVaults.toNumber() - 1;
+
+    const [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        new anchor.BN(latestVaultId).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    );
+    const vaultAccount = await program.account.vault.fetch(vault);
+    const [yieldTracker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yield_tracker"), vault.toBuffer()],
+      program.programId
+    );
+    const [pendingConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pending_config"), vault.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .closeVault()
+        .accounts({
+          vault,
+          yieldTracker,
+          shareMint: vaultAccount.shareMint,
+          vaultTokenAccount: vaultAccount.tokenAccount,
+          assetMint: vaultAccount.assetMint,
+          shareEscrow: null,
+          legacyShareEscrow: null,
+          pendingConfig,
+          ownerTokenAccount: null,
+          owner: vaultOwner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([vaultOwner])
+        .rpc();
+      expect.fail("Closing an active vault should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("VaultStillActive");
+    }
+  });
+
+  it("Rejects migrating accounts already at the current version", async () => {
+    try {
+      await program.methods
+        .migrateVaultStore()
+        .accounts({
+          vaultStore,
+          authority: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+      expect.fail("Migrating a current vault store should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("AccountVersionMismatch");
+    }
+  });
+
+  it("Configures inbound nonce ordering per source chain", async () => {
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const latestVaultId = vaultStoreAccount.totalVaults.toNumber() - 1;
+
+    const [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        new anchor.BN(latestVaultId).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    );
+    const srcChainId = 110;
+    const [inboundNonce] = PublicKey.findProgramAddressSync(
+      [Buffer.from("inbound_nonce"), vault.toBuffer(), new anchor.BN(srcChainId).toArrayLike(Buffer, "le", 2)],
+      program.programId
+    );
+
+    await program.methods
+      .setInboundNonceOrdering(srcChainId, { unordered: {} })
+      .accounts({
+        vault,
+        inboundNonce,
+        owner: vaultOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([vaultOwner])
+      .rpc();
+
+    const inboundNonceAccount = await program.account.inboundNonce.fetch(inboundNonce);
+    expect(inboundNonceAccount.srcChainId).to.equal(srcChainId);
+    expect(inboundNonceAccount.ordering).to.deep.equal({ unordered: {} });
+    expect(inboundNonceAccount.lastNonce.toNumber()).to.equal(0);
+    console.log("✅ Inbound nonce ordering configured");
+  });
+
+  it("Transfers vault ownership in two steps", async () => {
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const latestVaultId = vaultStoreAccount.totalVaults.toNumber() - 1;
+
+    const [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        new anchor.BN(latestVaultId).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    );
+    const newOwner = Keypair.generate();
+
+    await program.methods
+      .proposeVaultOwner(newOwner.publicKey)
+      .accounts({
+        vault,
+        owner: vaultOwner.publicKey,
+      })
+      .signers([vaultOwner])
+      .rpc();
+
+    let vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.pendingOwner.toString()).to.equal(newOwner.publicKey.toString());
+    expect(vaultAccount.owner.toString()).to.equal(vaultOwner.publicKey.toString());
+
+    // Only the proposed owner can accept
+    try {
+      await program.methods
+        .acceptVaultOwner()
+        .accounts({
+          vault,
+          pendingOwner: vaultOwner.publicKey,
+        })
+        .signers([vaultOwner])
+        .rpc();
+      expect.fail("Accept by a non-pending owner should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("UnauthorizedCaller");
+    }
+
+    await program.methods
+      .acceptVaultOwner()
+      .accounts({
+        vault,
+        pendingOwner: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+
+    // The vault keeps its address because the PDA is derived from the creator
+    vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.owner.toString()).to.equal(newOwner.publicKey.toString());
+    expect(vaultAccount.creator.toString()).to.equal(vaultOwner.publicKey.toString());
+    expect(vaultAccount.pendingOwner.toString()).to.equal(PublicKey.default.toString());
+    console.log("✅ Vault ownership transferred successfully");
+  });
+
+  it("Rejects keeper and guardian instructions from random signers", async () => {
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const latestVaultId = vaultStoreAccount.totalVaults.toNumber() - 1;
+
+    const [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        new anchor.BN(latestVaultId).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    );
+    const [yieldTracker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yield_tracker"), vault.toBuffer()],
+      program.programId
+    );
+    const randomSigner = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(randomSigner.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+
+    // Rebalance requires a keeper
+    try {
+      await program.methods
+        .rebalanceVault(110)
+        .accounts({
+          vault,
+          vaultStore,
+          roles,
+          authority: randomSigner.publicKey,
+        })
+        .signers([randomSigner])
+        .rpc();
+      expect.fail("Rebalance by a random signer should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("UnauthorizedCaller");
+    }
+
+    // Cross-chain yield queries require a keeper
+    try {
+      await program.methods
+        .queryCrossChainYields([101], new anchor.BN(anchor.web3.LAMPORTS_PER_SOL))
+        .accounts({
+          vault,
+          yieldTracker,
+          vaultStore,
+          roles,
+          endpoint: Keypair.generate().publicKey,
+          payer: randomSigner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([randomSigner])
+        .rpc();
+      expect.fail("Yield query by a random signer should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("UnauthorizedCaller");
+    }
+
+    // CCTP rebalances require a keeper
+    try {
+      await program.methods
+        .rebalanceWithCctp(0, new anchor.BN(1_000_000))
+        .accounts({
+          vault,
+          yieldTracker,
+          vaultStore,
+          cctpConfig: CCTP_CONFIG_FIXTURE,
+          roles,
+          authority: randomSigner.publicKey,
+          tokenMessenger: Keypair.generate().publicKey,
+        })
+        .signers([randomSigner])
+        .rpc();
+      expect.fail("CCTP rebalance by a random signer should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("UnauthorizedCaller");
+    }
+
+    // Emergency pause requires a guardian or the store authority
+    try {
+      await program.methods
+        .emergencyPause()
+        .accounts({
+          vaultStore,
+          roles,
+          guardian: randomSigner.publicKey,
+        })
+        .signers([randomSigner])
+        .rpc();
+      expect.fail("Emergency pause by a random signer should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("UnauthorizedCaller");
+    }
+
+    console.log("✅ Random signers rejected with UnauthorizedCaller");
+  });
+
+  it("Rejects lz_receive from unregistered peers, other endpoints and direct calls", async () => {
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const latestVaultId = vaultStoreAccount.totalVaults.toNumber() - 1;
+
+    const [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        new anchor.BN(latestVaultId).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    );
+    const [yieldTracker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yield_tracker"), vault.toBuffer()],
+      program.programId
+    );
+    const srcChainId = 101;
+    const [peer] = PublicKey.findProgramAddressSync(
+      [Buffer.from("peer"), new anchor.BN(srcChainId).toArrayLike(Buffer, "le", 2)],
+      program.programId
+    );
+    const [inboundNonce] = PublicKey.findProgramAddressSync(
+      [Buffer.from("inbound_nonce"), vault.toBuffer(), new anchor.BN(srcChainId).toArrayLike(Buffer, "le", 2)],
+      program.programId
+    );
+    const trustedSender = Array.from(Keypair.generate().publicKey.toBytes());
+    const spoofedSender = Array.from(Keypair.generate().publicKey.toBytes());
+
+    await program.methods
+      .setPeer(srcChainId, trustedSender)
+      .accounts({
+        peer,
+        vaultStore,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const peerAccount = await program.account.peer.fetch(peer);
+    expect(peerAccount.address).to.deep.equal(trustedSender);
+
+    const randomSigner = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(randomSigner.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+    const receiveParams = (sender: number[]) => ({
+      srcEid: srcChainId,
+      sender,
+      nonce: new anchor.BN(1),
+      guid: Array.from(Keypair.generate().publicKey.toBytes()),
+      message: Buffer.from([1]),
+      extraData: Buffer.alloc(0),
+    });
+    const receive = (sender: number[], endpoint: PublicKey, clearAccounts: PublicKey[]) =>
+      program.methods
+        .lzReceive(receiveParams(sender))
+        .accounts({
+          vault,
+          yieldTracker,
+          peer,
+          inboundNonce,
+          vaultStore,
+          endpoint,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          payer: randomSigner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          clearAccounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+        )
+        .signers([randomSigner])
+        .rpc();
+
+    // A sender other than the registered peer is rejected
+    try {
+      await receive(spoofedSender, LAYERZERO_ENDPOINT, [vaultStore]);
+      expect.fail("lz_receive with a spoofed sender should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("UnauthorizedPeer");
+    }
+
+    // An endpoint other than the LayerZero endpoint is rejected
+    try {
+      await receive(trustedSender, Keypair.generate().publicKey, [vaultStore]);
+      expect.fail("lz_receive with a spoofed endpoint should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidLayerZeroEndpoint");
+    }
+
+    // The executor reaches lz_receive through a CPI, so a direct call is rejected before the clear
+    try {
+      await receive(trustedSender, LAYERZERO_ENDPOINT, [vaultStore]);
+      expect.fail("lz_receive as a top-level instruction should be rejected");
+    } catch (err) {
+      if (err instanceof AssertionError) throw err;
+      expect(err.toString()).to.include("LzReceiveNotInvokedByCpi");
+    }
+    expect(await program.account.inboundNonce.fetchNullable(inboundNonce)).to.be.null;
+
+    console.log("✅ Spoofed senders, endpoints and direct calls rejected");
+  });
+
+  it("Stores executor options per destination chain", async () => {
+    const chainId = 101;
+    const [chainConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("chain_config"), new anchor.BN(chainId).toArrayLike(Buffer, "le", 2)],
+      program.programId
+    );
+    const executorOptions = {
+      lzReceiveGas: new anchor.BN(200_000),
+      lzReceiveValue: new anchor.BN(0),
+      nativeDropAmount: new anchor.BN(0),
+      nativeDropReceiver: Array(32).fill(0),
+      composeGas: new anchor.BN(0),
+      composeValue: new anchor.BN(0),
+    };
+
+    await program.methods
+      .setChainConfig(chainId, 30101, executorOptions)
+      .accounts({
+        chainConfig,
+        vaultStore,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const chainConfigAccount = await program.account.chainConfig.fetch(chainConfig);
+    expect(chainConfigAccount.chainId).to.equal(chainId);
+    expect(chainConfigAccount.eid).to.equal(30101);
+    expect(chainConfigAccount.executorOptions.lzReceiveGas.toNumber()).to.equal(200_000);
+
+    // A native drop needs a receiver
+    try {
+      await program.methods
+        .setChainConfig(chainId, 30101, { ...executorOptions, nativeDropAmount: new anchor.BN(1_000_000) })
+        .accounts({
+          chainConfig,
+          vaultStore,
+          authority: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+      expect.fail("A native drop without a receiver should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidExecutorOptions");
+    }
+  });
+
+  it("Creates a multisig and gates execution on its threshold", async () => {
+    const signerA = Keypair.generate();
+    const signerB = Keypair.generate();
+    const signerC = Keypair.generate();
+    const createKey = Keypair.generate().publicKey;
+
+    const [multisig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig"), createKey.toBuffer()],
+      program.programId
+    );
+    const [multisigSigner] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig_signer"), multisig.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createMultisig(createKey, [signerA.publicKey, signerB.publicKey, signerC.publicKey], 2)
+      .accounts({
+        multisig,
+        multisigSigner,
+        payer: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const multisigAccount = await program.account.multisig.fetch(multisig);
+    expect(multisigAccount.threshold).to.equal(2);
+    expect(multisigAccount.signers.length).to.equal(3);
+
+    // Propose an emergency pause signed by the multisig signer PDA
+    const [proposal] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig_proposal"), multisig.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const data = program.coder.instruction.encode("emergencyPause", {});
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(signerA.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+
+    await program.methods
+      .createMultisigProposal(
+        [
+          { pubkey: vaultStore, isSigner: false, isWritable: true },
+          { pubkey: roles, isSigner: false, isWritable: false },
+          { pubkey: multisigSigner, isSigner: true, isWritable: false },
+        ],
+        data
+      )
+      .accounts({
+        multisig,
+        proposal,
+        proposer: signerA.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([signerA])
+      .rpc();
+
+    let proposalAccount = await program.account.multisigProposal.fetch(proposal);
+    expect(proposalAccount.approvals).to.equal(0b001);
+
+    // One approval is below the 2-of-3 threshold
+    try {
+      await program.methods
+        .executeMultisigProposal()
+        .accounts({
+          multisig,
+          multisigSigner,
+          proposal,
+          executor: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+      expect.fail("Execution below the threshold should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("NotEnoughApprovals");
+    }
+
+    // Non-members cannot approve
+    try {
+      await program.methods
+        .approveMultisigProposal()
+        .accounts({
+          multisig,
+          proposal,
+          signer: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+      expect.fail("Approval by a non-member should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("UnauthorizedCaller");
+    }
+
+    await program.methods
+      .approveMultisigProposal()
+      .accounts({
+        multisig,
+        proposal,
+        signer: signerB.publicKey,
+      })
+      .signers([signerB])
+      .rpc();
+
+    proposalAccount = await program.account.multisigProposal.fetch(proposal);
+    expect(proposalAccount.approvals).to.equal(0b011);
+    expect(proposalAccount.executed).to.equal(false);
+    console.log("✅ Multisig created and proposal approved");
+  });
+
+  it("Wraps native SOL deposits into a wSOL vault", async () => {
+    const vault = await latestVault();
+    const vaultAccount = await program.account.vault.fetch(vault);
+    const user = await newFundedUser(3 * anchor.web3.LAMPORTS_PER_SOL);
+    const [userPosition] = PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), vault.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+    const userShareAccount = associatedTokenAddress(user.publicKey, vaultAccount.shareMint);
+    const amount = new anchor.BN(anchor.web3.LAMPORTS_PER_SOL);
+
+    // The wSOL vault is an SPL vault, so native SOL deposits are refused
+    try {
+      await program.methods
+        .depositSol(amount)
+        .accounts({
+          vault,
+          userPosition,
+          vaultStore,
+          user: user.publicKey,
+          shareMint: vaultAccount.shareMint,
+          userShareAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      expect.fail("deposit_sol into an SPL vault should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("AssetKindMismatch");
+    }
+
+    const vaultBalanceBefore = await tokenBalance(provider, vaultAccount.tokenAccount);
+    await program.methods
+      .depositWrappedSol(amount)
+      .accounts({
+        vault,
+        userPosition,
+        vaultStore,
+        user: user.publicKey,
+        vaultTokenAccount: vaultAccount.tokenAccount,
+        shareMint: vaultAccount.shareMint,
+        userShareAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    // The lamports land in the vault's wSOL account as synced tokens
+    const vaultBalanceAfter = await tokenBalance(provider, vaultAccount.tokenAccount);
+    expect(vaultBalanceAfter.sub(vaultBalanceBefore).toString()).to.equal(amount.toString());
+    const positionAccount = await program.account.userPosition.fetch(userPosition);
+    expect(positionAccount.amount.toString()).to.equal(amount.toString());
+    const shares = await tokenBalance(provider, userShareAccount);
+    expect(shares.toNumber()).to.be.greaterThan(0);
+    expect(positionAccount.shares.toString()).to.equal(shares.toString());
+    console.log("✅ Native SOL wrapped into the wSOL vault");
+  });
+
+  it("Keeps native SOL vault payouts above rent and accrued fees", async () => {
+    const wsolVaultAccount = await program.account.vault.fetch(await latestVault());
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        vaultStoreAccount.totalVaults.toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    );
+    const [yieldTracker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yield_tracker"), vault.toBuffer()],
+      program.programId
+    );
+    const [shareMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vault.toBuffer()],
+      program.programId
+    );
+    const [vaultTokenAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_tokens"), vault.toBuffer()],
+      program.programId
+    );
+    const createSolVault = (assetMint: PublicKey) =>
+      program.methods
+        .createVault(
+          { conservative: {} },
+          new anchor.BN(anchor.web3.LAMPORTS_PER_SOL / 10),
+          [101],
+          new anchor.BN(0),
+          { nativeSol: {} }
+        )
+        .accounts({
+          vault,
+          yieldTracker,
+          shareMint,
+          assetMint,
+          vaultTokenAccount,
+          vaultStore,
+          owner: vaultOwner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([vaultOwner])
+        .rpc();
+
+    // SOL vaults are denominated in lamports, so only the wSOL mint is accepted
+    try {
+      await createSolVault(wsolVaultAccount.shareMint);
+      expect.fail("A SOL vault over a non-wSOL mint should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("AssetKindMismatch");
+    }
+    await createSolVault(NATIVE_MINT);
+
+    // Additional assets are only taken by SPL vaults
+    const extraMint = wsolVaultAccount.shareMint;
+    const [vaultAsset] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_asset"), vault.toBuffer(), extraMint.toBuffer()],
+      program.programId
+    );
+    const [assetTokenAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("asset_tokens"), vaultAsset.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .addVaultAsset(new anchor.BN(1), new anchor.BN(0))
+        .accounts({
+          vault,
+          vaultAsset,
+          assetMint: extraMint,
+          assetTokenAccount,
+          vaultStore,
+          owner: vaultOwner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([vaultOwner])
+        .rpc();
+      expect.fail("A SOL vault should not take additional assets");
+    } catch (err) {
+      expect(err.toString()).to.include("AssetKindMismatch");
+    }
+
+    const user = await newFundedUser(3 * anchor.web3.LAMPORTS_PER_SOL);
+    const [userPosition] = PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), vault.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+    const userShareAccount = associatedTokenAddress(user.publicKey, shareMint);
+    const amount = 2 * anchor.web3.LAMPORTS_PER_SOL;
+    const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(
+      (await provider.connection.getAccountInfo(vault)).data.length
+    );
+
+    await program.methods
+      .depositSol(new anchor.BN(amount))
+      .accounts({
+        vault,
+        userPosition,
+        vaultStore,
+        user: user.publicKey,
+        shareMint,
+        userShareAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    expect(await provider.connection.getBalance(vault)).to.equal(rentFloor + amount);
+
+    const shares = await tokenBalance(provider, userShareAccount);
+    await program.methods
+      .withdrawSol(shares)
+      .accounts({
+        vault,
+        userPosition,
+        vaultStore,
+        user: user.publicKey,
+        shareMint,
+        userShareAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    // The withdrawal fee stays behind on top of the rent-exempt minimum
+    const feeRate = (await program.account.vaultStore.fetch(vaultStore)).feeRate;
+    const fee = Math.floor((amount * feeRate) / 10000);
+    const vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.accruedFees.toNumber()).to.equal(fee);
+    expect(vaultAccount.totalDeposits.toNumber()).to.equal(0);
+    expect(await provider.connection.getBalance(vault)).to.equal(rentFloor + fee);
+    console.log("✅ SOL vault paid out above rent and accrued fees");
+  });
+
+  it("Credits Token-2022 deposits with the amount received after the transfer fee", async () => {
+    // 1% transfer fee, withheld in the vault's token account
+    const mint = await createTransferFeeMint(provider, authority.publicKey, 6, 100, BigInt(1_000_000_000));
+
+    const vaultStoreAccount = await program.account.vaultStore.fetch(vaultStore);
+    const [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        vaultOwner.publicKey.toBuffer(),
+        vaultStoreAccount.totalVaults.toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    );
+    const [yieldTracker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yield_tracker"), vault.toBuffer()],
+      program.programId
+    );
+    const [shareMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vault.toBuffer()],
+      program.programId
+    );
+    const [vaultTokenAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_tokens"), vault.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createVault({ conservative: {} }, new anchor.BN(1_000_000), [101], new anchor.BN(0), { spl: {} })
+      .accounts({
+        vault,
+        yieldTracker,
+        shareMint,
+        assetMint: mint,
+        vaultTokenAccount,
+        vaultStore,
+        owner: vaultOwner.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([vaultOwner])
+      .rpc();
+
+    const user = await newFundedUser();
+    const userTokenAccount = await createAssociatedTokenAccount(provider, user.publicKey, mint, TOKEN_2022_PROGRAM_ID);
+    await mintTokens(provider, mint, userTokenAccount, authority, BigInt(100_000_000), TOKEN_2022_PROGRAM_ID);
+    const [userPosition] = PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), vault.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+    const userShareAccount = associatedTokenAddress(user.publicKey, shareMint, TOKEN_2022_PROGRAM_ID);
+
+    await program.methods
+      .deposit(new anchor.BN(100_000_000))
+      .accounts({
+        vault,
+        userPosition,
+        vaultStore,
+        user: user.publicKey,
+        userTokenAccount,
+        vaultTokenAccount,
+        shareMint,
+        userShareAccount,
+        assetMint: mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    // 1_000_000 of the 100_000_000 sent is withheld as the fee
+    const received = 99_000_000;
+    const vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.totalDeposits.toNumber()).to.equal(received);
+    expect((await tokenBalance(provider, vaultTokenAccount)).toNumber()).to.equal(received);
+    expect((await tokenBalance(provider, userShareAccount)).toNumber()).to.equal(received);
+    const positionAccount = await program.account.userPosition.fetch(userPosition);
+    expect(positionAccount.amount.toNumber()).to.equal(received);
+    expect(positionAccount.shares.toNumber()).to.equal(received);
+    console.log("✅ Token-2022 deposit credited net of the transfer fee");
+  });
 });